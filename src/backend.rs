@@ -0,0 +1,97 @@
+use std::env::temp_dir;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::debounce::claim_last_event;
+
+const STATE_PREFIX: &str = "yabai-utils";
+
+/// Everything commands and signal handlers read from or write to the outside world:
+/// yabai, the state persisted between invocations and the clock.
+/// Replays swap it for the recorded data, which keeps them deterministic.
+pub(crate) trait Backend: Send + Sync {
+    /// Output of `yabai -m <message>`
+    fn query(&self, message: &str) -> io::Result<Vec<u8>>;
+
+    /// Runs `yabai -m` with `message` as arguments
+    fn send(&self, message: &[&str]) -> io::Result<Output>;
+
+    /// Content of the state `name`, `None` when nothing has been saved yet
+    fn read_state(&self, name: &str) -> Option<Vec<u8>>;
+
+    fn write_state(&self, name: &str, content: &[u8]) -> io::Result<()>;
+
+    /// Seconds since the unix epoch
+    fn timestamp(&self) -> u64;
+
+    /// Whether no later event of `kind` arrived within `delay_ms`, see `is_last_event`
+    fn is_last_event(&self, kind: &str, delay_ms: u64) -> bool;
+
+    /// Whether to coordinate with other invocations, like notifying `status --watch`
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// The running yabai, state files in the temp directory and the system clock
+pub(crate) struct System;
+
+fn state_path(name: &str) -> PathBuf {
+    temp_dir().join(format!("{}-{}.json", STATE_PREFIX, name))
+}
+
+impl Backend for System {
+    fn query(&self, message: &str) -> io::Result<Vec<u8>> {
+        let output = Command::new("yabai")
+            .arg("-m")
+            .args(message.split_whitespace())
+            .output()?;
+        Ok(output.stdout)
+    }
+
+    fn send(&self, message: &[&str]) -> io::Result<Output> {
+        Command::new("yabai").arg("-m").args(message).output()
+    }
+
+    fn read_state(&self, name: &str) -> Option<Vec<u8>> {
+        fs::read(state_path(name)).ok()
+    }
+
+    fn write_state(&self, name: &str, content: &[u8]) -> io::Result<()> {
+        fs::write(state_path(name), content)
+    }
+
+    fn timestamp(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default()
+    }
+
+    fn is_last_event(&self, kind: &str, delay_ms: u64) -> bool {
+        claim_last_event(kind, delay_ms)
+    }
+}
+
+static BACKEND: Mutex<Option<Arc<dyn Backend>>> = Mutex::new(None);
+
+pub(crate) fn backend() -> Arc<dyn Backend> {
+    BACKEND
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| Arc::new(System))
+        .clone()
+}
+
+/// Routes everything through `backend` from now on, returning the previous one
+pub(crate) fn set_backend(backend: Arc<dyn Backend>) -> Arc<dyn Backend> {
+    BACKEND
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .replace(backend)
+        .unwrap_or_else(|| Arc::new(System))
+}
@@ -1,8 +1,14 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use clap::{Args, Parser, Subcommand};
 
-use crate::windows::{Direction, WindowId};
+use crate::completions::{CandidateKind, CompletionShell};
+use crate::grid::GridPlacement;
+use crate::layouts::Layout;
+use crate::spaces::{DirectionOrIndex, SpaceSelector};
+use crate::status::StatusFormat;
+use crate::windows::{Corner, Direction, ResizeAmount, WindowId, Zoom};
 
 #[derive(Parser)]
 #[command(version, about, long_about=None)]
@@ -19,6 +25,75 @@ pub enum Commands {
     Space(SpaceArgs),
     /// Signals,
     Signal(SignalArgs),
+    /// Debugging
+    Debug(DebugArgs),
+    /// Printing the spaces per display and the focused window for status bars
+    Status(StatusArgs),
+    /// Generating configuration for other tools
+    Generate(GenerateArgs),
+    /// Printing shell completions, space labels and app names are completed from yabai
+    Completions(CompletionsArgs),
+    /// Printing the man page
+    Man(ManArgs),
+    /// Printing completion candidates, used by the shell completions
+    #[command(hide = true)]
+    Candidates(CandidatesArgs),
+}
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    pub shell: CompletionShell,
+}
+
+#[derive(Args)]
+pub struct ManArgs {
+    /// Writing the pages of all subcommands into <OUT_DIR> instead
+    #[arg(short, long)]
+    pub out_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct CandidatesArgs {
+    pub kind: CandidateKind,
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[command(subcommand)]
+    pub command: GenerateCommand,
+}
+
+#[derive(Subcommand)]
+pub enum GenerateCommand {
+    /// Printing skhd bindings for the `keymap` of the config
+    Skhd,
+}
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// Printing the status again whenever a signal reports a change
+    #[arg(short, long)]
+    pub watch: bool,
+    #[arg(short, long, default_value = "json")]
+    pub format: StatusFormat,
+}
+
+#[derive(Args)]
+pub struct DebugArgs {
+    #[command(subcommand)]
+    pub command: DebugCommand,
+}
+
+#[derive(Subcommand)]
+pub enum DebugCommand {
+    /// Replaying the signal events of a trace against the recorded yabai state,
+    /// printing the actions taken
+    Replay(ReplayArgs),
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    pub trace: PathBuf,
 }
 #[derive(Args, Debug)]
 pub struct SignalArgs {
@@ -54,60 +129,260 @@ pub enum SpaceCommand {
     Focus(SpaceDirectionArgs),
     /// Destroying empty spaces
     DestroyAllEmpty,
+    /// Moving windows so that the occupied spaces of each display are contiguous
+    Compact,
+    /// Reordering the current space, cycling with directions
+    /// or moving it to the provided index
+    Move(SpaceDirectionArgs),
+    /// Sending the current space to another display, focus follows the space
+    SendDisplay(DisplayDirectionArgs),
+    /// Laying out the windows of the current space, turning it into a float space.
+    /// Without a layout the current one is applied again
+    Layout(SpaceLayoutArgs),
+}
+
+#[derive(Args)]
+pub struct SpaceLayoutArgs {
+    pub layout: Option<Layout>,
 }
 
 #[derive(Args)]
 #[group(required = true, multiple = false)]
 pub struct SpaceDirectionArgs {
     /// Provide a direction <left, right> or an index <number>
+    pub direction_or_index: Option<DirectionOrIndex>,
+    /// Selecting the space by its label instead
+    #[arg(long)]
+    pub label: Option<String>,
+}
+
+impl SpaceDirectionArgs {
+    pub fn selector(&self) -> SpaceSelector {
+        match (&self.direction_or_index, &self.label) {
+            (_, Some(label)) => SpaceSelector::Label(label.clone()),
+            (Some(direction_or_index), None) => {
+                SpaceSelector::DirectionOrIndex(direction_or_index.clone())
+            }
+            // the group requires one of them
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct DisplayDirectionArgs {
+    /// Provide a direction <left, right> or a display index <number>
     pub direction_or_index: DirectionOrIndex,
 }
 
+#[derive(Args)]
+pub struct WindowArgs {
+    #[command(subcommand)]
+    pub command: WindowCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WindowCommand {
+    Focus(WindowDirectionArgs),
+    /// Swapping the focused window with its neighbour
+    Swap(WindowDirectionArgs),
+    /// Re-inserting the focused window next to its neighbour
+    Warp(WindowDirectionArgs),
+    Resize(WindowResizeArgs),
+    Move(SpaceDirectionArgs),
+    AutoFocus,
+    /// Floating the window and placing it on a grid of its display
+    Grid(WindowGridArgs),
+    /// Hiding windows away and bringing them back on demand
+    Scratchpad(ScratchpadArgs),
+    /// Marking the focused window to jump back to it later
+    Mark(MarkArgs),
+    /// Focusing a marked window, switching spaces when necessary
+    FocusMark(MarkArgs),
+    /// Listing the marked windows as JSON
+    Marks,
+    /// Focusing a window of an application, launching it when it has no windows.
+    /// Repeated calls cycle through the windows of the application
+    FocusApp(FocusAppArgs),
+    /// Listing all windows as JSON, most recently focused first
+    List(WindowListArgs),
+    /// Focusing a window by id, switching to its space first
+    FocusId(YabaiWindowId),
+    /// Cycling through the focused stack or joining the stack of a neighbour
+    Stack(WindowStackArgs),
+    /// Toggling the zoom of the focused window
+    Zoom(WindowZoomArgs),
+    /// Managing floating windows
+    Float(WindowFloatArgs),
+    /// Managing sticky windows, shown on every space
+    Sticky(WindowStickyArgs),
+    /// Toggling picture-in-picture, a small sticky window pinned to a corner
+    Pip(WindowPipArgs),
+}
+
+#[derive(Args)]
+pub struct WindowStickyArgs {
+    #[command(subcommand)]
+    pub command: WindowStickyCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WindowStickyCommand {
+    /// Toggling whether the focused window is shown on every space
+    Toggle,
+}
+
+#[derive(clap::Args)]
+pub struct WindowPipArgs {
+    #[arg(default_value = "bottom-right")]
+    pub corner: Corner,
+}
+
+#[derive(Args)]
+pub struct WindowFloatArgs {
+    #[command(subcommand)]
+    pub command: WindowFloatCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WindowFloatCommand {
+    /// Toggling between floating and tiled, a window floats again at its previous frame
+    Toggle,
+    /// Centering the floating window on its display
+    Center,
+}
+
+#[derive(clap::Args)]
+pub struct WindowZoomArgs {
+    pub zoom: Zoom,
+}
+
+#[derive(clap::Args)]
+pub struct WindowStackArgs {
+    /// Provide <next, prev> to cycle or a direction <up, down, left, right> to join a neighbour
+    pub target: StackTarget,
+    #[command(flatten)]
+    pub sticky: StickyArgs,
+}
+
 #[derive(Debug, Clone)]
-pub enum DirectionOrIndex {
-    /// Selecting the space to the Left
-    Left,
-    /// Selecting the space to the Right
-    Right,
-    /// Going to space with <index>
-    Index(u8),
+pub enum StackTarget {
+    Next,
+    Prev,
+    Direction(Direction),
 }
 
-impl FromStr for DirectionOrIndex {
+impl FromStr for StackTarget {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Ok(index) = s.parse::<u8>() {
-            Ok(DirectionOrIndex::Index(index))
-        } else if s.to_lowercase() == "left" {
-            Ok(DirectionOrIndex::Left)
-        } else if s.to_lowercase() == "right" {
-            Ok(DirectionOrIndex::Right)
-        } else {
-            Err(format!("invalid value: {}", s))
+        match s.to_lowercase().as_str() {
+            "next" => Ok(StackTarget::Next),
+            "prev" => Ok(StackTarget::Prev),
+            "up" => Ok(StackTarget::Direction(Direction::Up)),
+            "down" => Ok(StackTarget::Direction(Direction::Down)),
+            "left" => Ok(StackTarget::Direction(Direction::Left)),
+            "right" => Ok(StackTarget::Direction(Direction::Right)),
+            _ => Err(format!("invalid value: {}", s)),
         }
     }
 }
 
 #[derive(Args)]
-pub struct WindowArgs {
+pub struct WindowListArgs {
+    /// Printing `id<TAB>app<TAB>title<TAB>space` lines for pickers like choose or fzf
+    #[arg(short, long)]
+    pub picker: bool,
+}
+
+#[derive(Args)]
+pub struct FocusAppArgs {
+    /// Application name as reported by yabai
+    pub app: String,
+    /// Shell command launching the application, defaults to the configured one or `open -a <app>`
+    #[arg(short, long)]
+    pub launch: Option<String>,
+}
+
+#[derive(Args)]
+pub struct MarkArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct ScratchpadArgs {
     #[command(subcommand)]
-    pub command: WindowCommand,
+    pub command: ScratchpadCommand,
 }
 
 #[derive(Subcommand)]
-pub enum WindowCommand {
-    Focus(WindowDirectionArgs),
-    Resize(WindowResizeDirectionArgs),
-    Move(SpaceDirectionArgs),
-    AutoFocus,
+pub enum ScratchpadCommand {
+    /// Turning the focused window into a scratchpad and hiding it
+    Send(ScratchpadNameArgs),
+    /// Showing the scratchpad centered on the current space or hiding it again
+    Toggle(ScratchpadNameArgs),
+}
+
+#[derive(Args)]
+pub struct ScratchpadNameArgs {
+    #[arg(default_value = "default")]
+    pub name: String,
+}
+
+#[derive(clap::Args)]
+pub struct WindowGridArgs {
+    /// <rows>:<cols>:<x>:<y>:<w>:<h> or a preset <full, left-half, right-half, top-half,
+    /// bottom-half, left-third, center-third, right-third, left-two-thirds, right-two-thirds, center>
+    pub placement: GridPlacement,
 }
 
 #[derive(clap::Args)]
 pub struct WindowDirectionArgs {
     pub direction: Direction,
-    #[arg(short, long, default_value_t = true)]
-    pub ignore_sticky: bool,
+    #[command(flatten)]
+    pub sticky: StickyArgs,
+}
+
+#[derive(clap::Args)]
+pub struct StickyArgs {
+    /// Considering sticky windows as neighbours, the default
+    #[arg(long, overrides_with = "exclude_sticky")]
+    pub include_sticky: bool,
+    /// Skipping sticky windows
+    #[arg(long, overrides_with = "include_sticky")]
+    pub exclude_sticky: bool,
+}
+
+impl StickyArgs {
+    pub fn include_sticky(&self) -> bool {
+        !self.exclude_sticky
+    }
+}
+
+#[derive(clap::Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct WindowResizeArgs {
+    #[command(subcommand)]
+    pub command: Option<WindowResizeCommand>,
+    #[command(flatten)]
+    pub edges: WindowResizeDirectionArgs,
+}
+
+#[derive(Subcommand)]
+pub enum WindowResizeCommand {
+    /// Growing the window towards a direction,
+    /// using the opposite edge when there is no room left
+    Grow(WindowSmartResizeArgs),
+    /// Shrinking the window towards a direction,
+    /// using the opposite edge when the edge can't move
+    Shrink(WindowSmartResizeArgs),
+}
+
+#[derive(clap::Args)]
+pub struct WindowSmartResizeArgs {
+    pub direction: Direction,
+    /// Amount in pixels <number> or relative to the display <number>%
+    pub amount: ResizeAmount,
 }
 
 #[derive(clap::Args)]
@@ -0,0 +1,215 @@
+use std::io;
+use std::process::{ExitCode, Output};
+use std::sync::Arc;
+
+use clap::Parser;
+
+use crate::backend::{backend, set_backend, Backend};
+
+use crate::clap::{Cli, Commands, WindowDirectionArgs};
+use crate::windows::{resize_window, smart_resize_window, Direction};
+
+use crate::clap::{
+    DebugCommand, GenerateCommand, ScratchpadCommand, SignalCommand, SpaceCommand, StackTarget,
+    WindowCommand, WindowFloatCommand, WindowResizeArgs, WindowResizeCommand,
+    WindowResizeDirectionArgs, WindowStackArgs, WindowStickyCommand,
+};
+use crate::completions::{print_candidates, print_completions, print_man_page};
+use crate::floating::{center_floating_window, toggle_float, toggle_pip, toggle_sticky};
+use crate::generate::generate_skhd;
+use crate::grid::place_window_on_grid;
+use crate::layouts::set_space_layout;
+use crate::log::{debug, info, set_context, warn};
+use crate::marks::{focus_mark, list_marks, mark_window};
+use crate::scratchpad::{send_to_scratchpad, toggle_scratchpad};
+use crate::signal::{load_signal, unload_signal};
+use crate::spaces::{
+    compact_spaces, destroy_all_empty, focus_space, move_space, send_space_to_display,
+};
+use crate::status::print_status;
+use crate::switcher::{focus_window_id, list_windows};
+use crate::trace::{replay_trace, traced_signal_event_handler};
+use crate::windows::{
+    auto_focus, focus_or_launch_app, focus_stack, focus_window_by_direction, move_window_to_space,
+    stack_window_by_direction, swap_window_by_direction, toggle_zoom, warp_window_by_direction,
+};
+
+/// Logs the commands sent to yabai, which the library leaves to its users
+struct Logging(Arc<dyn Backend>);
+
+impl Backend for Logging {
+    fn query(&self, message: &str) -> io::Result<Vec<u8>> {
+        self.0.query(message)
+    }
+
+    fn send(&self, message: &[&str]) -> io::Result<Output> {
+        debug(format!("yabai -m {}", message.join(" ")));
+        self.0.send(message)
+    }
+
+    fn read_state(&self, name: &str) -> Option<Vec<u8>> {
+        self.0.read_state(name)
+    }
+
+    fn write_state(&self, name: &str, content: &[u8]) -> io::Result<()> {
+        self.0.write_state(name, content)
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.0.timestamp()
+    }
+
+    fn is_last_event(&self, kind: &str, delay_ms: u64) -> bool {
+        self.0.is_last_event(kind, delay_ms)
+    }
+
+    fn is_live(&self) -> bool {
+        self.0.is_live()
+    }
+}
+
+/// Entry point of the `yabai_utils` binary
+pub fn run() -> ExitCode {
+    set_context(std::env::args().skip(1).collect::<Vec<_>>().join(" "));
+    set_backend(Arc::new(Logging(backend())));
+    let cli = Cli::try_parse();
+    let cli = match cli {
+        Ok(x) => x,
+        Err(e) => {
+            // help and version requests end up here as well
+            if e.use_stderr() {
+                warn(e.to_string());
+            } else {
+                info(e.to_string());
+            }
+            let _ = e.print();
+            return ExitCode::FAILURE;
+        }
+    };
+    match &cli.command {
+        Commands::Window(x) => match &x.command {
+            WindowCommand::Focus(WindowDirectionArgs { direction, sticky }) => {
+                focus_window_by_direction(direction, sticky.include_sticky())
+            }
+            WindowCommand::Swap(WindowDirectionArgs { direction, sticky }) => {
+                swap_window_by_direction(direction, sticky.include_sticky())
+            }
+            WindowCommand::Warp(WindowDirectionArgs { direction, sticky }) => {
+                warp_window_by_direction(direction, sticky.include_sticky())
+            }
+            WindowCommand::Resize(WindowResizeArgs {
+                command: Some(command),
+                ..
+            }) => match command {
+                WindowResizeCommand::Grow(arg) => {
+                    smart_resize_window(true, &arg.direction, &arg.amount)
+                }
+                WindowResizeCommand::Shrink(arg) => {
+                    smart_resize_window(false, &arg.direction, &arg.amount)
+                }
+            },
+            WindowCommand::Resize(WindowResizeArgs {
+                command: None,
+                edges:
+                    WindowResizeDirectionArgs {
+                        left,
+                        right,
+                        up,
+                        down,
+                    },
+            }) => {
+                if let Some(left) = left {
+                    resize_window(Direction::Left, *left)
+                }
+                if let Some(right) = right {
+                    resize_window(Direction::Right, *right)
+                }
+                if let Some(up) = up {
+                    resize_window(Direction::Up, *up)
+                }
+                if let Some(down) = down {
+                    resize_window(Direction::Down, *down)
+                }
+            }
+            WindowCommand::Move(arg) => move_window_to_space(&arg.selector(), true),
+            WindowCommand::AutoFocus => auto_focus(),
+            WindowCommand::Grid(arg) => place_window_on_grid(&arg.placement),
+            WindowCommand::Mark(arg) => mark_window(&arg.name),
+            WindowCommand::FocusMark(arg) => focus_mark(&arg.name),
+            WindowCommand::Marks => list_marks(),
+            WindowCommand::List(arg) => list_windows(arg.picker),
+            WindowCommand::FocusId(arg) => focus_window_id(arg.window_id),
+            WindowCommand::Float(arg) => match arg.command {
+                WindowFloatCommand::Toggle => toggle_float(),
+                WindowFloatCommand::Center => center_floating_window(),
+            },
+            WindowCommand::Sticky(arg) => match arg.command {
+                WindowStickyCommand::Toggle => toggle_sticky(),
+            },
+            WindowCommand::Pip(arg) => toggle_pip(&arg.corner),
+            WindowCommand::Zoom(arg) => toggle_zoom(&arg.zoom),
+            WindowCommand::Stack(WindowStackArgs { target, sticky }) => match target {
+                StackTarget::Next => focus_stack(true),
+                StackTarget::Prev => focus_stack(false),
+                StackTarget::Direction(direction) => {
+                    stack_window_by_direction(direction, sticky.include_sticky())
+                }
+            },
+            WindowCommand::FocusApp(arg) => focus_or_launch_app(&arg.app, arg.launch.as_deref()),
+            WindowCommand::Scratchpad(arg) => match &arg.command {
+                ScratchpadCommand::Send(arg) => send_to_scratchpad(&arg.name),
+                ScratchpadCommand::Toggle(arg) => toggle_scratchpad(&arg.name),
+            },
+        },
+        Commands::Space(arg) => match &arg.command {
+            SpaceCommand::Focus(arg) => focus_space(&arg.selector()),
+            SpaceCommand::DestroyAllEmpty => destroy_all_empty(),
+            SpaceCommand::Compact => compact_spaces(),
+            SpaceCommand::Move(arg) => move_space(&arg.selector()),
+            SpaceCommand::SendDisplay(arg) => send_space_to_display(&arg.direction_or_index),
+            SpaceCommand::Layout(arg) => set_space_layout(arg.layout),
+        },
+        Commands::Signal(arg) => {
+            info(format!("Signal {:?}", arg.command));
+            match &arg.command {
+                SignalCommand::Load => load_signal(),
+                SignalCommand::Unload => unload_signal(),
+                SignalCommand::Event(signal) => {
+                    traced_signal_event_handler(&signal.event, std::env::args().skip(3).collect())
+                }
+            }
+        }
+        Commands::Debug(arg) => match &arg.command {
+            DebugCommand::Replay(arg) => {
+                if let Err(e) = replay_trace(&arg.trace) {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        },
+        Commands::Status(arg) => {
+            if let Err(e) = print_status(arg.format, arg.watch) {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        Commands::Completions(arg) => print_completions(arg.shell),
+        Commands::Man(arg) => {
+            if let Err(e) = print_man_page(arg.out_dir.as_deref()) {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        Commands::Candidates(arg) => print_candidates(arg.kind),
+        Commands::Generate(arg) => match &arg.command {
+            GenerateCommand::Skhd => match generate_skhd() {
+                Ok(skhd) => print!("{}", skhd),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            },
+        },
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::spaces::SpaceIndex;
+use crate::windows::{new_window_order, WindowId, WindowNeighbours, Zoom};
+use crate::yabai::{
+    query_yabai, query_yabai_object, send_yabai_args, YabaiCommandError, YabaiDisplayObject,
+    YabaiQueryError, YabaiSignal, YabaiSpaceObject, YabaiWindowFrame, YabaiWindowObject,
+};
+
+/// Typed access to the running yabai, every call goes through `yabai -m`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Client;
+
+impl Client {
+    pub fn new() -> Self {
+        Client
+    }
+
+    /// Windows on all spaces
+    pub fn windows(&self) -> Result<Vec<YabaiWindowObject>, YabaiQueryError> {
+        query_yabai("query --windows")
+    }
+
+    /// Windows on the space with `space_index`, the focused space without one
+    pub fn space_windows(
+        &self,
+        space_index: Option<SpaceIndex>,
+    ) -> Result<Vec<YabaiWindowObject>, YabaiQueryError> {
+        match space_index {
+            Some(space_index) => query_yabai(&format!("query --windows --space {}", space_index)),
+            None => query_yabai("query --windows --space"),
+        }
+    }
+
+    pub fn window(&self, window_id: WindowId) -> Result<YabaiWindowObject, YabaiQueryError> {
+        query_yabai_object(&format!("query --windows --window {}", window_id))
+    }
+
+    pub fn spaces(&self) -> Result<Vec<YabaiSpaceObject>, YabaiQueryError> {
+        query_yabai("query --spaces")
+    }
+
+    pub fn displays(&self) -> Result<Vec<YabaiDisplayObject>, YabaiQueryError> {
+        query_yabai("query --displays")
+    }
+
+    /// Signals registered with yabai, including the ones of other tools
+    pub fn signals(&self) -> Result<Vec<YabaiSignal>, YabaiQueryError> {
+        query_yabai("signal --list")
+    }
+
+    /// Directional neighbours of the windows on the focused space, as used by `window focus`
+    pub fn window_order(&self) -> Result<HashMap<WindowId, WindowNeighbours>, YabaiQueryError> {
+        let mut windows = self.space_windows(None)?;
+        windows.retain(|x| x.is_visible && !x.is_hidden);
+        Ok(new_window_order(&windows))
+    }
+
+    fn send(&self, message: &[&str]) -> Result<(), YabaiCommandError> {
+        let output =
+            send_yabai_args(message.to_vec()).map_err(YabaiCommandError::CommandExecutionError)?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(YabaiCommandError::Rejected(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    pub fn focus_window(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", "--focus", &window_id.to_string()])
+    }
+
+    /// Focuses a window by selector like `stack.next`
+    pub fn focus_window_selector(&self, selector: &str) -> Result<(), YabaiCommandError> {
+        self.send(&["window", "--focus", selector])
+    }
+
+    /// Stacks `window_id` onto `target`, joining the stack `target` is part of
+    pub fn stack_window_onto(
+        &self,
+        target: WindowId,
+        window_id: WindowId,
+    ) -> Result<(), YabaiCommandError> {
+        self.send(&[
+            "window",
+            &target.to_string(),
+            "--stack",
+            &window_id.to_string(),
+        ])
+    }
+
+    /// Swaps the focused window with `window_id`
+    pub fn swap_window(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", "--swap", &window_id.to_string()])
+    }
+
+    /// Re-inserts the focused window next to `window_id`
+    pub fn warp_window(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", "--warp", &window_id.to_string()])
+    }
+
+    /// Toggles the zoom of the focused window
+    pub fn toggle_zoom(&self, zoom: &Zoom) -> Result<(), YabaiCommandError> {
+        let toggle = match zoom {
+            Zoom::Parent => "zoom-parent",
+            Zoom::Fullscreen => "zoom-fullscreen",
+            Zoom::Native => "native-fullscreen",
+        };
+        self.send(&["window", "--toggle", toggle])
+    }
+
+    pub fn set_window_frame(
+        &self,
+        window_id: WindowId,
+        frame: &YabaiWindowFrame,
+    ) -> Result<(), YabaiCommandError> {
+        let window = window_id.to_string();
+        self.send(&[
+            "window",
+            &window,
+            "--move",
+            &format!("abs:{:.0}:{:.0}", frame.x, frame.y),
+        ])?;
+        self.send(&[
+            "window",
+            &window,
+            "--resize",
+            &format!("abs:{:.0}:{:.0}", frame.w, frame.h),
+        ])
+    }
+
+    pub fn toggle_float(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", &window_id.to_string(), "--toggle", "float"])
+    }
+
+    pub fn toggle_sticky(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", &window_id.to_string(), "--toggle", "sticky"])
+    }
+
+    pub fn minimize_window(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", &window_id.to_string(), "--minimize"])
+    }
+
+    pub fn deminimize_window(&self, window_id: WindowId) -> Result<(), YabaiCommandError> {
+        self.send(&["window", "--deminimize", &window_id.to_string()])
+    }
+
+    pub fn move_window_to_space(
+        &self,
+        window_id: WindowId,
+        space_index: SpaceIndex,
+    ) -> Result<(), YabaiCommandError> {
+        self.send(&[
+            "window",
+            &window_id.to_string(),
+            "--space",
+            &space_index.to_string(),
+        ])
+    }
+
+    pub fn focus_space(&self, space_index: SpaceIndex) -> Result<(), YabaiCommandError> {
+        self.send(&["space", "--focus", &space_index.to_string()])
+    }
+
+    /// Creates a space at the end of the focused display
+    pub fn create_space(&self) -> Result<(), YabaiCommandError> {
+        self.send(&["space", "--create"])
+    }
+
+    pub fn destroy_space(&self, space_index: SpaceIndex) -> Result<(), YabaiCommandError> {
+        self.send(&["space", &space_index.to_string(), "--destroy"])
+    }
+
+    /// Moves the focused space to `space_index`
+    pub fn move_space(&self, space_index: SpaceIndex) -> Result<(), YabaiCommandError> {
+        self.send(&["space", "--move", &space_index.to_string()])
+    }
+
+    /// Sends the focused space to the display with `display_index`
+    pub fn send_space_to_display(&self, display_index: u8) -> Result<(), YabaiCommandError> {
+        self.send(&["space", "--display", &display_index.to_string()])
+    }
+
+    /// Labels a space, an empty label removes it
+    pub fn label_space(
+        &self,
+        space_index: SpaceIndex,
+        label: &str,
+    ) -> Result<(), YabaiCommandError> {
+        self.send(&["space", &space_index.to_string(), "--label", label])
+    }
+
+    /// Sets the yabai layout of a space, `bsp`, `stack` or `float`
+    pub fn set_space_layout(
+        &self,
+        space_index: SpaceIndex,
+        layout: &str,
+    ) -> Result<(), YabaiCommandError> {
+        self.send(&["space", &space_index.to_string(), "--layout", layout])
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+use clap::CommandFactory;
+use clap_complete::{generate, Shell};
+
+use crate::clap::Cli;
+use crate::client::Client;
+use crate::config::config;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Values completed from the live yabai state
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CandidateKind {
+    /// Space labels
+    Labels,
+    /// Running applications and the ones configured in `launch`
+    Apps,
+}
+
+const BASH_CANDIDATES: &str = r#"
+_yabai_utils_candidates() {
+    local kind
+    case "${COMP_WORDS[1]} ${COMP_WORDS[2]}" in
+        "space focus" | "space move" | "window move")
+            [[ "${COMP_WORDS[COMP_CWORD-1]}" == "--label" ]] && kind=labels ;;
+        "window focus-app")
+            [[ ${COMP_CWORD} -eq 3 ]] && kind=apps ;;
+    esac
+    if [[ -z "${kind}" ]]; then
+        _yabai_utils "$@"
+        return
+    fi
+    local IFS=$'\n' candidate
+    COMPREPLY=()
+    for candidate in $(compgen -W "$(yabai_utils candidates ${kind} 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}"); do
+        COMPREPLY+=("$(printf '%q' "${candidate}")")
+    done
+}
+
+complete -F _yabai_utils_candidates -o bashdefault -o default yabai_utils
+"#;
+
+const ZSH_CANDIDATES: &str = r#"
+# Source this script, e.g. `source <(yabai_utils completions zsh)` in .zshrc.
+# Autoloaded from fpath the first completion runs without live candidates.
+_yabai_utils_candidates() {
+    local kind
+    case "${words[2]} ${words[3]}" in
+        "space focus" | "space move" | "window move")
+            [[ "${words[CURRENT-1]}" == "--label" ]] && kind=labels ;;
+        "window focus-app")
+            (( CURRENT == 4 )) && kind=apps ;;
+    esac
+    if [[ -z "${kind}" ]]; then
+        _yabai_utils "$@"
+        return
+    fi
+    local -a candidates
+    candidates=("${(@f)$(yabai_utils candidates ${kind} 2>/dev/null)}")
+    compadd -a candidates
+}
+
+compdef _yabai_utils_candidates yabai_utils
+"#;
+
+const FISH_CANDIDATES: &str = r#"
+complete -c yabai_utils -n "__fish_seen_subcommand_from space; and __fish_seen_subcommand_from focus move" -l label -x -a "(yabai_utils candidates labels 2>/dev/null)"
+complete -c yabai_utils -n "__fish_seen_subcommand_from window; and __fish_seen_subcommand_from move" -l label -x -a "(yabai_utils candidates labels 2>/dev/null)"
+complete -c yabai_utils -n "__fish_seen_subcommand_from window; and __fish_seen_subcommand_from focus-app" -f -a "(yabai_utils candidates apps 2>/dev/null)"
+"#;
+
+fn static_completions(shell: Shell) -> String {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut buffer = Vec::new();
+    generate(shell, &mut command, name, &mut buffer);
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+/// Wraps the completions clap generates, which only know static values, to complete
+/// `candidates` where they apply and defer to clap everywhere else
+pub fn print_completions(shell: CompletionShell) {
+    match shell {
+        CompletionShell::Bash => {
+            print!("{}{}", static_completions(Shell::Bash), BASH_CANDIDATES);
+        }
+        CompletionShell::Zsh => {
+            print!("{}{}", static_completions(Shell::Zsh), ZSH_CANDIDATES);
+        }
+        CompletionShell::Fish => {
+            print!("{}{}", static_completions(Shell::Fish), FISH_CANDIDATES);
+        }
+    }
+}
+
+/// Prints the top level man page, or writes the pages of all commands to `out_dir`
+pub fn print_man_page(out_dir: Option<&Path>) -> io::Result<()> {
+    match out_dir {
+        Some(out_dir) => clap_mangen::generate_to(Cli::command(), out_dir),
+        None => clap_mangen::Man::new(Cli::command()).render(&mut io::stdout()),
+    }
+}
+
+/// Prints one completion candidate per line, nothing when yabai can't be queried
+pub fn print_candidates(kind: CandidateKind) {
+    let candidates: BTreeSet<String> = match kind {
+        CandidateKind::Labels => Client
+            .spaces()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.label)
+            .filter(|x| !x.is_empty())
+            .collect(),
+        CandidateKind::Apps => Client
+            .windows()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.app)
+            .chain(config().launch.keys().cloned())
+            .collect(),
+    };
+    for candidate in candidates {
+        println!("{}", candidate);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::log::{error, Level, LogFormat};
+
+const CONFIG_DIRNAME: &str = "yabai-utils";
+const CONFIG_FILENAME: &str = "config.json";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub spaces: SpacePolicy,
+    pub grid: GridSettings,
+    pub layouts: LayoutSettings,
+    pub floating: FloatingSettings,
+    pub log: LogSettings,
+    pub trace: TraceSettings,
+    pub debounce: DebounceSettings,
+    /// Commands launching an application by app name, used by `window focus-app`
+    pub launch: HashMap<String, String>,
+    /// Commands by skhd hotkey, like `alt - h`, used by `generate skhd`
+    pub keymap: BTreeMap<String, String>,
+}
+
+/// Controls how spaces are tidied up, most notably which empty spaces
+/// `destroy_all_empty` is allowed to remove
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SpacePolicy {
+    /// Never destroy spaces that carry a label
+    pub keep_labelled: bool,
+    /// Number of leading spaces on each display that are never destroyed
+    pub keep_first: usize,
+    /// Only destroy the empty spaces at the end of a display
+    pub trailing_only: bool,
+    /// Never destroy the focused space
+    pub keep_focused: bool,
+    /// Seconds a freshly created space is protected from being destroyed
+    pub grace_period: u64,
+    /// Compact the occupied spaces whenever a window gets destroyed
+    pub compact_on_destroy: bool,
+}
+
+impl Default for SpacePolicy {
+    fn default() -> Self {
+        SpacePolicy {
+            keep_labelled: true,
+            keep_first: 0,
+            trailing_only: false,
+            keep_focused: true,
+            grace_period: 5,
+            compact_on_destroy: false,
+        }
+    }
+}
+
+/// Spacing used when placing floating windows
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GridSettings {
+    /// Pixels kept free along the display edges
+    pub padding: f64,
+    /// Pixels kept free between neighbouring cells
+    pub gap: f64,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        GridSettings {
+            padding: 10.0,
+            gap: 10.0,
+        }
+    }
+}
+
+/// Settings for the layouts of float spaces, these share the spacing of `grid`
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LayoutSettings {
+    /// Share of the display width taken by the master window
+    pub master_ratio: f64,
+    /// Pixels at the top of every display covered by the menu bar or a status bar
+    pub menu_bar_height: f64,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        LayoutSettings {
+            master_ratio: 0.6,
+            menu_bar_height: 25.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FloatingSettings {
+    /// Center newly created floating windows on their display
+    pub center_new: bool,
+    /// Width of picture-in-picture windows relative to the display
+    pub pip_scale: f64,
+}
+
+impl Default for FloatingSettings {
+    fn default() -> Self {
+        FloatingSettings {
+            center_new: true,
+            pip_scale: 0.25,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LogSettings {
+    /// Log file, defaults to `yabai-utils.log` in the temp directory
+    pub path: Option<PathBuf>,
+    /// Most verbose level that gets logged, `YABAI_UTILS_LOG` takes precedence
+    pub level: Level,
+    pub format: LogFormat,
+    /// Size in bytes after which the log file gets rotated
+    pub max_size: u64,
+    /// Number of rotated log files to keep
+    pub keep: usize,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            path: None,
+            level: Level::Info,
+            format: LogFormat::Text,
+            max_size: 1024 * 1024,
+            keep: 3,
+        }
+    }
+}
+
+/// Recording of signal events for `debug replay`
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TraceSettings {
+    /// Off unless asked for, traces hold window titles and grow with every signal
+    pub enabled: bool,
+    /// Trace file, defaults to `yabai-utils-trace.jsonl` in the temp directory
+    pub path: Option<PathBuf>,
+    /// Size in bytes after which the trace file gets rotated
+    pub max_size: u64,
+    /// Number of rotated trace files to keep
+    pub keep: usize,
+}
+
+impl Default for TraceSettings {
+    fn default() -> Self {
+        TraceSettings {
+            enabled: false,
+            path: None,
+            max_size: 10 * 1024 * 1024,
+            keep: 1,
+        }
+    }
+}
+
+/// Milliseconds signal events wait for further events of the same type,
+/// only the last event of a burst does the expensive work
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DebounceSettings {
+    pub window_created: u64,
+    pub window_destroyed: u64,
+    pub window_minimized: u64,
+    pub window_moved: u64,
+}
+
+impl Default for DebounceSettings {
+    fn default() -> Self {
+        DebounceSettings {
+            window_created: 0,
+            window_destroyed: 150,
+            window_minimized: 150,
+            window_moved: 150,
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("YABAI_UTILS_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join(CONFIG_DIRNAME).join(CONFIG_FILENAME))
+}
+
+fn load_config() -> Result<Config, String> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    let Ok(content) = fs::read(&path) else {
+        return Ok(Config::default());
+    };
+    serde_json::from_slice(&content)
+        .map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
+
+pub fn config() -> &'static Config {
+    if let Some(config) = CONFIG.get() {
+        return config;
+    }
+    match load_config() {
+        Ok(config) => CONFIG.get_or_init(|| config),
+        Err(e) => {
+            // logging reads the config as well, so it has to be set first
+            let config = CONFIG.get_or_init(Config::default);
+            error(e);
+            config
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::env::temp_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::backend::backend;
+use crate::log::{debug, warn};
+
+const DEBOUNCE_PREFIX: &str = "yabai-utils-debounce";
+
+fn token_path(kind: &str) -> PathBuf {
+    temp_dir().join(format!("{}-{}", DEBOUNCE_PREFIX, kind))
+}
+
+/// Unique for every handler invocation
+fn token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", process::id(), nanos)
+}
+
+/// Tells whether this is the last event of `kind` within `delay_ms`.
+/// Only the last event of a burst gets to do the expensive work.
+pub fn is_last_event(kind: &str, delay_ms: u64) -> bool {
+    if delay_ms == 0 {
+        return true;
+    }
+    backend().is_last_event(kind, delay_ms)
+}
+
+/// Claims the latest event of `kind`, waits `delay_ms` and tells whether no other
+/// invocation claimed it in the meantime
+pub(crate) fn claim_last_event(kind: &str, delay_ms: u64) -> bool {
+    let path = token_path(kind);
+    let token = token();
+    if let Err(e) = fs::write(&path, &token) {
+        warn(format!("Failed to debounce {}: {}", kind, e));
+        return true;
+    }
+    thread::sleep(Duration::from_millis(delay_ms));
+    match fs::read_to_string(&path) {
+        Ok(latest) if latest != token => {
+            debug(format!("Coalesced {} into a later event", kind));
+            false
+        }
+        _ => true,
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config;
+use crate::layouts::layout_area;
+use crate::state::{load_state, save_state};
+use crate::windows::{focused_window, window_display, Corner, WindowId};
+use crate::yabai::{
+    query_window, query_windows, yabai_set_window_frame, yabai_toggle_float, yabai_toggle_sticky,
+    YabaiWindowFrame, YabaiWindowObject,
+};
+
+const FLOAT_FRAMES_STATE: &str = "float-frames";
+const PIP_STATE: &str = "pip";
+
+/// Last floating frame of windows that got tiled
+type FloatFrames = HashMap<WindowId, YabaiWindowFrame>;
+
+/// How a window looked before it went picture-in-picture
+#[derive(Serialize, Deserialize, Debug)]
+struct PipWindow {
+    frame: YabaiWindowFrame,
+    was_floating: bool,
+    was_sticky: bool,
+}
+
+type PipWindows = HashMap<WindowId, PipWindow>;
+
+fn center_on_display(window: &YabaiWindowObject) {
+    if let Some(display) = window_display(window) {
+        yabai_set_window_frame(window.id, &window.frame.centered_in(&display.frame));
+    }
+}
+
+/// Tiles a floating window, remembering its frame, or floats a tiled window,
+/// restoring the remembered frame or centering it on its display
+pub fn toggle_float() {
+    let windows = query_windows();
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let mut frames: FloatFrames = load_state(FLOAT_FRAMES_STATE);
+    yabai_toggle_float(window.id);
+    if window.is_floating {
+        frames.insert(window.id, window.frame.clone());
+    } else {
+        match frames.remove(&window.id) {
+            Some(frame) => yabai_set_window_frame(window.id, &frame),
+            None => {
+                if let Some(window) = query_window(window.id) {
+                    center_on_display(&window);
+                }
+            }
+        }
+    }
+    save_state(FLOAT_FRAMES_STATE, &frames);
+}
+
+/// Centers the focused window on its display, if it is floating
+pub fn center_floating_window() {
+    let windows = query_windows();
+    if let Some(window) = focused_window(&windows).filter(|x| x.is_floating) {
+        center_on_display(window);
+    }
+}
+
+/// Centers a newly created window on its display, if it is floating
+pub fn center_new_window(window_id: WindowId) {
+    if let Some(window) = query_window(window_id).filter(|x| x.is_floating && !x.is_sticky) {
+        center_on_display(&window);
+    }
+}
+
+pub fn toggle_sticky() {
+    let windows = query_windows();
+    if let Some(window) = focused_window(&windows) {
+        yabai_toggle_sticky(window.id);
+    }
+}
+
+/// Shrinks the focused window, makes it sticky and pins it to `corner`.
+/// A window already in picture-in-picture is restored instead.
+pub fn toggle_pip(corner: &Corner) {
+    let windows = query_windows();
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let mut pip_windows: PipWindows = load_state(PIP_STATE);
+
+    if let Some(pip) = pip_windows.remove(&window.id) {
+        if !pip.was_sticky && window.is_sticky {
+            yabai_toggle_sticky(window.id);
+        }
+        if pip.was_floating {
+            yabai_set_window_frame(window.id, &pip.frame);
+        } else if window.is_floating {
+            yabai_toggle_float(window.id);
+        }
+        save_state(PIP_STATE, &pip_windows);
+        return;
+    }
+
+    // the aspect ratio is undefined for windows without a size
+    if window.frame.w <= 0.0 || window.frame.h <= 0.0 {
+        return;
+    }
+    let Some(display) = window_display(window) else {
+        return;
+    };
+    pip_windows.insert(
+        window.id,
+        PipWindow {
+            frame: window.frame.clone(),
+            was_floating: window.is_floating,
+            was_sticky: window.is_sticky,
+        },
+    );
+    if !window.is_floating {
+        yabai_toggle_float(window.id);
+    }
+    if !window.is_sticky {
+        yabai_toggle_sticky(window.id);
+    }
+    let area = layout_area(
+        &display.frame,
+        config().layouts.menu_bar_height,
+        config().grid.padding,
+    );
+    let w = display.frame.w * config().floating.pip_scale;
+    let h = w * window.frame.h / window.frame.w;
+    let (x, y) = match corner {
+        Corner::TopLeft => (area.x, area.y),
+        Corner::TopRight => (area.x + area.w - w, area.y),
+        Corner::BottomLeft => (area.x, area.y + area.h - h),
+        Corner::BottomRight => (area.x + area.w - w, area.y + area.h - h),
+    };
+    yabai_set_window_frame(window.id, &YabaiWindowFrame { x, y, w, h });
+    save_state(PIP_STATE, &pip_windows);
+}
+
+/// Drops the remembered frames of a destroyed window
+pub fn forget_float_frame(window_id: WindowId) {
+    let mut frames: FloatFrames = load_state(FLOAT_FRAMES_STATE);
+    if frames.remove(&window_id).is_some() {
+        save_state(FLOAT_FRAMES_STATE, &frames);
+    }
+    let mut pip_windows: PipWindows = load_state(PIP_STATE);
+    if pip_windows.remove(&window_id).is_some() {
+        save_state(PIP_STATE, &pip_windows);
+    }
+}
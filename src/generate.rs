@@ -0,0 +1,62 @@
+use clap::Parser;
+
+use crate::clap::Cli;
+use crate::config::config;
+
+const BINARY_NAME: &str = "yabai_utils";
+
+/// Splits a command like a shell would, honouring single and double quotes
+fn split_args(command: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    args.extend(current.take());
+    Ok(args)
+}
+
+fn validate_command(command: &str) -> Result<(), String> {
+    let args = split_args(command)?;
+    Cli::try_parse_from([BINARY_NAME.to_string()].into_iter().chain(args))
+        .map(|_| ())
+        .map_err(|e| {
+            // the first line carries the reason, the rest is usage
+            e.to_string().lines().next().unwrap_or_default().to_string()
+        })
+}
+
+/// Renders the configured keymap as skhd bindings, failing on commands the CLI rejects
+pub fn generate_skhd() -> Result<String, String> {
+    let keymap = &config().keymap;
+    let errors: Vec<String> = keymap
+        .iter()
+        .filter_map(|(keys, command)| {
+            validate_command(command)
+                .err()
+                .map(|e| format!("{}: {}: {}", keys, command, e))
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let mut skhd = format!("# generated by `{} generate skhd`\n", BINARY_NAME);
+    for (keys, command) in keymap {
+        skhd.push_str(&format!("{} : {} {}\n", keys, BINARY_NAME, command));
+    }
+    Ok(skhd)
+}
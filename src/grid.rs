@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+use crate::config::config;
+use crate::layouts::layout_area;
+use crate::windows::{focused_window, window_display};
+use crate::yabai::{query_windows, yabai_set_window_frame, yabai_toggle_float, YabaiWindowFrame};
+
+/// Placement of a window on a grid of `rows` x `cols` cells,
+/// starting at cell `x`:`y` and spanning `w`:`h` cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridPlacement {
+    pub rows: u32,
+    pub cols: u32,
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl GridPlacement {
+    fn new(rows: u32, cols: u32, x: u32, y: u32, w: u32, h: u32) -> Self {
+        GridPlacement {
+            rows,
+            cols,
+            x,
+            y,
+            w,
+            h,
+        }
+    }
+
+    fn preset(name: &str) -> Option<Self> {
+        let placement = match name {
+            "full" => GridPlacement::new(1, 1, 0, 0, 1, 1),
+            "left-half" => GridPlacement::new(1, 2, 0, 0, 1, 1),
+            "right-half" => GridPlacement::new(1, 2, 1, 0, 1, 1),
+            "top-half" => GridPlacement::new(2, 1, 0, 0, 1, 1),
+            "bottom-half" => GridPlacement::new(2, 1, 0, 1, 1, 1),
+            "left-third" => GridPlacement::new(1, 3, 0, 0, 1, 1),
+            "center-third" => GridPlacement::new(1, 3, 1, 0, 1, 1),
+            "right-third" => GridPlacement::new(1, 3, 2, 0, 1, 1),
+            "left-two-thirds" => GridPlacement::new(1, 3, 0, 0, 2, 1),
+            "right-two-thirds" => GridPlacement::new(1, 3, 1, 0, 2, 1),
+            "center" => GridPlacement::new(6, 6, 1, 1, 4, 4),
+            _ => return None,
+        };
+        Some(placement)
+    }
+
+    /// Computes the frame inside `area`, see `layout_area`
+    /// edges and `gap` between neighbouring cells
+    pub fn frame(&self, area: &YabaiWindowFrame, gap: f64) -> YabaiWindowFrame {
+        let cell_w = (area.w - (self.cols - 1) as f64 * gap) / self.cols as f64;
+        let cell_h = (area.h - (self.rows - 1) as f64 * gap) / self.rows as f64;
+        YabaiWindowFrame {
+            x: area.x + self.x as f64 * (cell_w + gap),
+            y: area.y + self.y as f64 * (cell_h + gap),
+            w: self.w as f64 * cell_w + (self.w - 1) as f64 * gap,
+            h: self.h as f64 * cell_h + (self.h - 1) as f64 * gap,
+        }
+    }
+}
+
+impl FromStr for GridPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(placement) = GridPlacement::preset(&s.to_lowercase()) {
+            return Ok(placement);
+        }
+        let values: Vec<u32> = s
+            .split(':')
+            .map(|x| x.parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid value: {}", s))?;
+        let [rows, cols, x, y, w, h] = values[..] else {
+            return Err(format!("expected <rows>:<cols>:<x>:<y>:<w>:<h>, got {}", s));
+        };
+        if rows == 0
+            || cols == 0
+            || w == 0
+            || h == 0
+            || x >= cols
+            || w > cols - x
+            || y >= rows
+            || h > rows - y
+        {
+            return Err(format!("placement {} doesn't fit on the grid", s));
+        }
+        Ok(GridPlacement::new(rows, cols, x, y, w, h))
+    }
+}
+
+/// Floats the focused window and places it on the grid of its display
+pub fn place_window_on_grid(placement: &GridPlacement) {
+    let windows = query_windows();
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let Some(display) = window_display(window) else {
+        return;
+    };
+    if !window.is_floating {
+        yabai_toggle_float(window.id);
+    }
+    let grid = &config().grid;
+    let area = layout_area(
+        &display.frame,
+        config().layouts.menu_bar_height,
+        grid.padding,
+    );
+    let frame = placement.frame(&area, grid.gap);
+    yabai_set_window_frame(window.id, &frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_placements() {
+        assert_eq!(
+            "2:3:1:0:2:2".parse(),
+            Ok(GridPlacement::new(2, 3, 1, 0, 2, 2))
+        );
+        assert_eq!(
+            "Left-Half".parse(),
+            Ok(GridPlacement::new(1, 2, 0, 0, 1, 1))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_placements() {
+        assert!("1:1:0:0:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:0:0:1:1:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:0:0:-1:1".parse::<GridPlacement>().is_err());
+        assert!("somewhere".parse::<GridPlacement>().is_err());
+    }
+
+    #[test]
+    fn rejects_placements_off_the_grid() {
+        assert!("2:2:2:0:1:1".parse::<GridPlacement>().is_err());
+        assert!("2:2:1:0:2:1".parse::<GridPlacement>().is_err());
+        assert!("2:2:0:1:1:2".parse::<GridPlacement>().is_err());
+        assert!("2:2:1:1:1:1".parse::<GridPlacement>().is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_sizes() {
+        assert!("0:1:0:0:1:1".parse::<GridPlacement>().is_err());
+        assert!("1:0:0:0:1:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:0:0:0:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:0:0:1:0".parse::<GridPlacement>().is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_placements() {
+        assert!("1:1:4294967295:0:1:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:0:4294967295:1:1".parse::<GridPlacement>().is_err());
+        assert!("1:1:1:0:4294967295:1".parse::<GridPlacement>().is_err());
+        assert!("4294967295:4294967295:4294967295:0:1:1"
+            .parse::<GridPlacement>()
+            .is_err());
+    }
+
+    #[test]
+    fn frames_keep_gaps() {
+        let area = YabaiWindowFrame {
+            x: 10.0,
+            y: 35.0,
+            w: 980.0,
+            h: 580.0,
+        };
+        let frame = GridPlacement::new(1, 2, 1, 0, 1, 1).frame(&area, 20.0);
+        assert_eq!(
+            (frame.x, frame.y, frame.w, frame.h),
+            (510.0, 35.0, 480.0, 580.0)
+        );
+    }
+
+    #[test]
+    fn full_placement_fills_the_layout_area() {
+        let display = YabaiWindowFrame {
+            x: 0.0,
+            y: 0.0,
+            w: 1000.0,
+            h: 600.0,
+        };
+        let area = layout_area(&display, 25.0, 10.0);
+        let frame = GridPlacement::preset("full").unwrap().frame(&area, 10.0);
+        assert_eq!(
+            (frame.x, frame.y, frame.w, frame.h),
+            (10.0, 35.0, 980.0, 555.0)
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config;
+use crate::state::{load_state, save_state};
+use crate::windows::WindowId;
+use crate::yabai::{
+    query_displays, query_space_windows, query_spaces, yabai_set_space_layout,
+    yabai_set_window_frame, SpaceType, YabaiSpaceObject, YabaiWindowFrame,
+};
+
+const LAYOUTS_STATE: &str = "layouts";
+/// Bounds of the master ratio, beyond them the master or the stack vanishes
+const MASTER_RATIO_BOUNDS: (f64, f64) = (0.1, 0.9);
+
+/// Layouts computed by yabai-utils for float spaces
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Leaving the windows where they are
+    Float,
+    /// One master window on the left, the others stacked on the right
+    MasterStack,
+    /// Equally wide columns
+    Columns,
+    /// Master window in the center, the others stacked on both sides
+    CenteredMaster,
+    /// Every window fills the whole display
+    Monocle,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SpaceLayout {
+    layout: Layout,
+    /// Order in which the windows get placed, the first one is the master
+    order: Vec<WindowId>,
+}
+
+/// Layouts by space uuid
+type SpaceLayouts = HashMap<String, SpaceLayout>;
+
+/// Splits `area` into `count` rows, separated by `gap`
+fn rows(area: &YabaiWindowFrame, count: usize, gap: f64) -> Vec<YabaiWindowFrame> {
+    let h = (area.h - (count - 1) as f64 * gap) / count as f64;
+    (0..count)
+        .map(|i| YabaiWindowFrame {
+            x: area.x,
+            y: area.y + i as f64 * (h + gap),
+            w: area.w,
+            h,
+        })
+        .collect()
+}
+
+/// Splits `area` into `count` columns, separated by `gap`
+fn columns(area: &YabaiWindowFrame, count: usize, gap: f64) -> Vec<YabaiWindowFrame> {
+    let w = (area.w - (count - 1) as f64 * gap) / count as f64;
+    (0..count)
+        .map(|i| YabaiWindowFrame {
+            x: area.x + i as f64 * (w + gap),
+            y: area.y,
+            w,
+            h: area.h,
+        })
+        .collect()
+}
+
+/// Part of `display` available to windows, below the menu bar and inside `padding`
+pub fn layout_area(
+    display: &YabaiWindowFrame,
+    menu_bar_height: f64,
+    padding: f64,
+) -> YabaiWindowFrame {
+    YabaiWindowFrame {
+        x: display.x + padding,
+        y: display.y + menu_bar_height + padding,
+        w: display.w - 2.0 * padding,
+        h: display.h - menu_bar_height - 2.0 * padding,
+    }
+}
+
+/// Computes the frames for `count` windows, in placement order
+pub fn layout_frames(
+    layout: Layout,
+    area: &YabaiWindowFrame,
+    count: usize,
+    gap: f64,
+    master_ratio: f64,
+) -> Vec<YabaiWindowFrame> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let master_ratio = master_ratio.clamp(MASTER_RATIO_BOUNDS.0, MASTER_RATIO_BOUNDS.1);
+    match layout {
+        Layout::Float => Vec::new(),
+        Layout::Monocle => vec![area.clone(); count],
+        Layout::Columns => columns(area, count, gap),
+        Layout::MasterStack | Layout::CenteredMaster if count == 1 => vec![area.clone()],
+        Layout::MasterStack => {
+            let master_w = (area.w - gap) * master_ratio;
+            let stack = YabaiWindowFrame {
+                x: area.x + master_w + gap,
+                y: area.y,
+                w: area.w - master_w - gap,
+                h: area.h,
+            };
+            let mut frames = vec![YabaiWindowFrame {
+                w: master_w,
+                ..area.clone()
+            }];
+            frames.append(&mut rows(&stack, count - 1, gap));
+            frames
+        }
+        Layout::CenteredMaster if count == 2 => {
+            layout_frames(Layout::MasterStack, area, count, gap, master_ratio)
+        }
+        Layout::CenteredMaster => {
+            let master_w = (area.w - 2.0 * gap) * master_ratio;
+            let side_w = (area.w - 2.0 * gap - master_w) / 2.0;
+            let left = YabaiWindowFrame {
+                w: side_w,
+                ..area.clone()
+            };
+            let right = YabaiWindowFrame {
+                x: area.x + side_w + master_w + 2.0 * gap,
+                w: side_w,
+                ..area.clone()
+            };
+            // the stack alternates between the right and the left side
+            let stack = count - 1;
+            let mut right = rows(&right, stack.div_ceil(2), gap).into_iter();
+            let mut left = rows(&left, stack / 2, gap).into_iter();
+            let mut frames = vec![YabaiWindowFrame {
+                x: area.x + side_w + gap,
+                w: master_w,
+                ..area.clone()
+            }];
+            for i in 0..stack {
+                let side = if i % 2 == 0 { &mut right } else { &mut left };
+                frames.extend(side.next());
+            }
+            frames
+        }
+    }
+}
+
+fn apply_layout(space: &YabaiSpaceObject, space_layout: &mut SpaceLayout) {
+    if space_layout.layout == Layout::Float || space.space_type != SpaceType::Float {
+        return;
+    }
+    let Some(display) = query_displays()
+        .into_iter()
+        .find(|x| x.index == space.display)
+    else {
+        return;
+    };
+    let mut windows = query_space_windows(space.index);
+    windows.retain(|x| x.is_visible && !x.is_hidden && !x.is_minimized && !x.is_sticky);
+
+    // keep the known order, newly created windows join at the end
+    space_layout
+        .order
+        .retain(|id| windows.iter().any(|x| x.id == *id));
+    windows.sort_by_key(|x| x.id);
+    for window in &windows {
+        if !space_layout.order.contains(&window.id) {
+            space_layout.order.push(window.id);
+        }
+    }
+
+    let (grid, settings) = (&config().grid, &config().layouts);
+    let area = layout_area(&display.frame, settings.menu_bar_height, grid.padding);
+    let frames = layout_frames(
+        space_layout.layout,
+        &area,
+        space_layout.order.len(),
+        grid.gap,
+        settings.master_ratio,
+    );
+    for (window_id, frame) in space_layout.order.iter().zip(frames) {
+        yabai_set_window_frame(*window_id, &frame);
+    }
+}
+
+/// Assigns `layout` to the focused space, or re-applies its current layout
+pub fn set_space_layout(layout: Option<Layout>) {
+    let spaces_infos = query_spaces();
+    let Some(space) = spaces_infos.iter().find(|x| x.has_focus) else {
+        return;
+    };
+    let mut layouts: SpaceLayouts = load_state(LAYOUTS_STATE);
+    let space_layout = layouts.entry(space.uuid.clone()).or_insert(SpaceLayout {
+        layout: Layout::Float,
+        order: Vec::new(),
+    });
+    if let Some(layout) = layout {
+        space_layout.layout = layout;
+    }
+    if space.space_type != SpaceType::Float {
+        yabai_set_space_layout(space.index, "float");
+    }
+    // the layout type changed, so query the space again
+    if let Some(space) = query_spaces().iter().find(|x| x.id == space.id) {
+        apply_layout(space, space_layout);
+    }
+    save_state(LAYOUTS_STATE, &layouts);
+}
+
+/// Re-applies the layouts of all visible spaces
+pub fn relayout_visible_spaces() {
+    let mut layouts: SpaceLayouts = load_state(LAYOUTS_STATE);
+    if layouts.is_empty() {
+        return;
+    }
+    for space in query_spaces().iter().filter(|x| x.is_visible) {
+        if let Some(space_layout) = layouts.get_mut(&space.uuid) {
+            apply_layout(space, space_layout);
+        }
+    }
+    save_state(LAYOUTS_STATE, &layouts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(x: f64, y: f64, w: f64, h: f64) -> YabaiWindowFrame {
+        YabaiWindowFrame { x, y, w, h }
+    }
+
+    fn frames_of(layout: Layout, count: usize, master_ratio: f64) -> Vec<(f64, f64, f64, f64)> {
+        let area = frame(0.0, 0.0, 1010.0, 610.0);
+        layout_frames(layout, &area, count, 10.0, master_ratio)
+            .iter()
+            .map(|x| (x.x, x.y, x.w, x.h))
+            .collect()
+    }
+
+    #[test]
+    fn area_leaves_menu_bar_and_padding_free() {
+        let area = layout_area(&frame(1440.0, 0.0, 1440.0, 900.0), 25.0, 10.0);
+        assert_eq!(
+            (area.x, area.y, area.w, area.h),
+            (1450.0, 35.0, 1420.0, 855.0)
+        );
+    }
+
+    #[test]
+    fn no_windows_no_frames() {
+        for layout in [Layout::MasterStack, Layout::Columns, Layout::Monocle] {
+            assert!(frames_of(layout, 0, 0.5).is_empty());
+        }
+    }
+
+    #[test]
+    fn float_layout_places_nothing() {
+        assert!(frames_of(Layout::Float, 3, 0.5).is_empty());
+    }
+
+    #[test]
+    fn single_window_fills_the_area() {
+        let full = vec![(0.0, 0.0, 1010.0, 610.0)];
+        for layout in [
+            Layout::MasterStack,
+            Layout::CenteredMaster,
+            Layout::Columns,
+            Layout::Monocle,
+        ] {
+            assert_eq!(frames_of(layout, 1, 0.5), full, "{:?}", layout);
+        }
+    }
+
+    #[test]
+    fn master_stack() {
+        assert_eq!(
+            frames_of(Layout::MasterStack, 2, 0.6),
+            vec![(0.0, 0.0, 600.0, 610.0), (610.0, 0.0, 400.0, 610.0)]
+        );
+        assert_eq!(
+            frames_of(Layout::MasterStack, 3, 0.6),
+            vec![
+                (0.0, 0.0, 600.0, 610.0),
+                (610.0, 0.0, 400.0, 300.0),
+                (610.0, 310.0, 400.0, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn centered_master() {
+        assert_eq!(
+            frames_of(Layout::CenteredMaster, 2, 0.5),
+            frames_of(Layout::MasterStack, 2, 0.5)
+        );
+        // the stack alternates between the right and the left side
+        assert_eq!(
+            frames_of(Layout::CenteredMaster, 4, 0.5),
+            vec![
+                (257.5, 0.0, 495.0, 610.0),
+                (762.5, 0.0, 247.5, 300.0),
+                (0.0, 0.0, 247.5, 610.0),
+                (762.5, 310.0, 247.5, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn columns() {
+        assert_eq!(
+            frames_of(Layout::Columns, 2, 0.5),
+            vec![(0.0, 0.0, 500.0, 610.0), (510.0, 0.0, 500.0, 610.0)]
+        );
+        let frames = frames_of(Layout::Columns, 5, 0.5);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[4], (816.0, 0.0, 194.0, 610.0));
+    }
+
+    #[test]
+    fn monocle() {
+        assert_eq!(
+            frames_of(Layout::Monocle, 3, 0.5),
+            vec![(0.0, 0.0, 1010.0, 610.0); 3]
+        );
+    }
+
+    #[test]
+    fn master_ratio_is_bounded() {
+        assert_eq!(
+            frames_of(Layout::MasterStack, 2, 1.5),
+            frames_of(Layout::MasterStack, 2, 0.9)
+        );
+        assert_eq!(
+            frames_of(Layout::MasterStack, 2, -1.0),
+            frames_of(Layout::MasterStack, 2, 0.1)
+        );
+        for ratio in [-1.0, 0.0, 1.0, 2.0] {
+            for layout in [Layout::MasterStack, Layout::CenteredMaster] {
+                for (_, _, w, h) in frames_of(layout, 3, ratio) {
+                    assert!(w > 0.0 && h > 0.0, "{:?} {}", layout, ratio);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Utilities on top of the yabai window manager: directional navigation that
+//! understands floating windows and stacks, space housekeeping and signal handlers.
+//!
+//! [`Client`] gives typed access to yabai. The `yabai_utils` binary is a thin
+//! wrapper around the commands of this crate.
+
+mod backend;
+mod clap;
+mod cli;
+pub mod client;
+mod completions;
+mod config;
+mod debounce;
+pub mod floating;
+mod generate;
+pub mod grid;
+pub mod layouts;
+mod lock;
+mod log;
+pub mod marks;
+pub mod scratchpad;
+pub mod signal;
+pub mod spaces;
+mod state;
+mod status;
+pub mod switcher;
+mod trace;
+pub mod windows;
+pub mod yabai;
+
+#[doc(hidden)]
+pub use cli::run;
+pub use client::Client;
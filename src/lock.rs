@@ -0,0 +1,35 @@
+use std::env::temp_dir;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::log::warn;
+
+const SPACES_LOCK_FILENAME: &str = "yabai-utils-spaces.lock";
+
+/// Exclusive lock on the spaces, released when dropped
+pub struct SpacesLock {
+    _file: File,
+}
+
+fn lock_path() -> PathBuf {
+    temp_dir().join(Path::new(SPACES_LOCK_FILENAME))
+}
+
+/// Blocks until no other invocation is creating, moving or destroying spaces.
+/// Space indexes queried before taking the lock can't be trusted.
+/// Without a lock file the operation goes ahead unguarded.
+pub fn lock_spaces() -> Option<SpacesLock> {
+    let result = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path())
+        .and_then(|file| file.lock().map(|_| file));
+    match result {
+        Ok(file) => Some(SpacesLock { _file: file }),
+        Err(e) => {
+            warn(format!("Failed to lock spaces: {}", e));
+            None
+        }
+    }
+}
@@ -1,20 +1,249 @@
-use std::env::temp_dir;
-use std::fs::File;
+use std::env::{self, temp_dir};
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::config::config;
 
 const LOG_FILENAME: &str = "yabai-utils.log";
+const LOG_ENV: &str = "YABAI_UTILS_LOG";
+
+static CONTEXT: OnceLock<String> = OnceLock::new();
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("invalid value: {}", s)),
+        }
+    }
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    Text,
+    JsonLines,
+}
+
+/// Sets the command or signal all following messages originate from
+pub fn set_context(context: String) {
+    let _ = CONTEXT.set(context);
+}
+
+/// `YABAI_UTILS_LOG` overrides the configured level, `off` disables logging
+fn max_level() -> Option<Level> {
+    match env::var(LOG_ENV) {
+        Ok(filter) if filter.eq_ignore_ascii_case("off") => None,
+        Ok(filter) => Some(filter.parse().unwrap_or(config().log.level)),
+        Err(_) => Some(config().log.level),
+    }
+}
+
+pub fn log_path() -> PathBuf {
+    config()
+        .log
+        .path
+        .clone()
+        .unwrap_or_else(|| temp_dir().join(Path::new(LOG_FILENAME)))
+}
+
+/// Moves `path` to `path.1`, shifting older files up to `path.<keep>`
+pub fn rotate(path: &Path, keep: usize) {
+    let rotated = |i: usize| PathBuf::from(format!("{}.{}", path.display(), i));
+    if keep == 0 {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    let _ = fs::remove_file(rotated(keep));
+    for i in (1..keep).rev() {
+        let _ = fs::rename(rotated(i), rotated(i + 1));
+    }
+    let _ = fs::rename(path, rotated(1));
+}
+
+/// Formats a unix time as RFC 3339 in UTC
+pub fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let days = (secs / 86400) as i64;
+    let (hour, minute, second) = (secs % 86400 / 3600, secs % 3600 / 60, secs % 60);
+
+    // civil date from days since 1970-01-01, after Howard Hinnant
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        since_epoch.subsec_millis()
+    )
+}
 
-fn load_file() -> Result<File, std::io::Error> {
-    let filepath = temp_dir().join(Path::new(LOG_FILENAME));
-    let file = File::options().create(true).append(true).open(filepath)?;
-    Ok(file)
+fn format_line(level: Level, msg: &str) -> String {
+    let timestamp = format_timestamp(SystemTime::now());
+    let context = CONTEXT.get().map(String::as_str).unwrap_or_default();
+    match config().log.format {
+        LogFormat::Text => format!("{} {:<5} [{}] {}", timestamp, level.name(), context, msg),
+        LogFormat::JsonLines => serde_json::json!({
+            "timestamp": timestamp,
+            "level": level.name(),
+            "context": context,
+            "message": msg,
+        })
+        .to_string(),
+    }
+}
+
+fn write_line(line: &str) -> Result<(), std::io::Error> {
+    let settings = &config().log;
+    let path = log_path();
+    let mut file = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    if file.is_none() {
+        *file = Some(File::options().create(true).append(true).open(&path)?);
+    }
+    let size = file
+        .as_ref()
+        .map_or(Ok(0), |x| x.metadata().map(|x| x.len()))?;
+    if size >= settings.max_size {
+        rotate(&path, settings.keep);
+        *file = Some(File::options().create(true).append(true).open(&path)?);
+    }
+    match file.as_mut() {
+        Some(file) => writeln!(file, "{}", line),
+        None => Ok(()),
+    }
 }
 
-pub fn log(msg: String) {
-    let file = load_file();
-    match file {
-        Ok(file) => writeln!(&file, "{}", msg).unwrap(),
-        Err(e) => println!("{}", e),
+pub fn log(level: Level, msg: String) {
+    if max_level().is_none_or(|max_level| level > max_level) {
+        return;
+    }
+    if let Err(e) = write_line(&format_line(level, &msg)) {
+        eprintln!("yabai-utils: failed to log: {}", e);
+    }
+}
+
+pub fn error(msg: String) {
+    log(Level::Error, msg)
+}
+
+pub fn warn(msg: String) {
+    log(Level::Warn, msg)
+}
+
+pub fn info(msg: String) {
+    log(Level::Info, msg)
+}
+
+pub fn debug(msg: String) {
+    log(Level::Debug, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn at(secs: u64, millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_timestamp(at(0, 0)), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(at(946684799, 999)),
+            "1999-12-31T23:59:59.999Z"
+        );
+        assert_eq!(
+            format_timestamp(at(4107628799, 0)),
+            "2100-03-01T23:59:59.000Z"
+        );
+    }
+
+    #[test]
+    fn formats_leap_days() {
+        assert_eq!(
+            format_timestamp(at(1709210096, 789)),
+            "2024-02-29T12:34:56.789Z"
+        );
+        assert_eq!(
+            format_timestamp(at(951782400, 0)),
+            "2000-02-29T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn rotates_keeping_old_files() {
+        let dir = temp_dir().join(format!("yabai-utils-test-rotate-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+        let rotated = |i: usize| PathBuf::from(format!("{}.{}", path.display(), i));
+        let read = |path: &Path| fs::read_to_string(path).ok();
+
+        for content in ["first", "second", "third"] {
+            fs::write(&path, content).unwrap();
+            rotate(&path, 2);
+        }
+        assert_eq!(read(&path), None);
+        assert_eq!(read(&rotated(1)).as_deref(), Some("third"));
+        assert_eq!(read(&rotated(2)).as_deref(), Some("second"));
+        assert_eq!(read(&rotated(3)), None);
+
+        fs::write(&path, "fourth").unwrap();
+        rotate(&path, 0);
+        assert_eq!(read(&path), None);
+        assert_eq!(read(&rotated(1)).as_deref(), Some("third"));
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
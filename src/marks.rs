@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::state::{load_state, save_state};
+use crate::windows::{focus_window_anywhere, focused_window, WindowId};
+use crate::yabai::{query_all_windows, query_window, query_windows};
+
+const MARKS_STATE: &str = "marks";
+
+/// Marked windows by name
+type Marks = BTreeMap<String, WindowId>;
+
+#[derive(Serialize)]
+struct MarkEntry<'a> {
+    name: &'a str,
+    id: WindowId,
+    app: &'a str,
+    title: &'a str,
+    space: usize,
+}
+
+/// Marks the focused window as `name`, a window can carry several marks
+pub fn mark_window(name: &str) {
+    let windows = query_windows();
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let mut marks: Marks = load_state(MARKS_STATE);
+    marks.insert(name.to_string(), window.id);
+    save_state(MARKS_STATE, &marks);
+}
+
+/// Focuses the window marked as `name`, switching spaces when necessary
+pub fn focus_mark(name: &str) {
+    let mut marks: Marks = load_state(MARKS_STATE);
+    let Some(window_id) = marks.get(name).copied() else {
+        return;
+    };
+    match query_window(window_id) {
+        Some(window) => focus_window_anywhere(&window),
+        None => {
+            marks.remove(name);
+            save_state(MARKS_STATE, &marks);
+        }
+    }
+}
+
+/// Prints the marks of all existing windows as JSON
+pub fn list_marks() {
+    let marks: Marks = load_state(MARKS_STATE);
+    let windows = query_all_windows();
+    let entries: Vec<MarkEntry> = marks
+        .iter()
+        .filter_map(|(name, window_id)| {
+            let window = windows.iter().find(|x| x.id == *window_id)?;
+            Some(MarkEntry {
+                name,
+                id: window.id,
+                app: &window.app,
+                title: &window.title,
+                space: window.space,
+            })
+        })
+        .collect();
+    match serde_json::to_string(&entries) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Drops the marks of a destroyed window
+pub fn forget_marked_window(window_id: WindowId) {
+    let mut marks: Marks = load_state(MARKS_STATE);
+    let count = marks.len();
+    marks.retain(|_, x| *x != window_id);
+    if marks.len() != count {
+        save_state(MARKS_STATE, &marks);
+    }
+}
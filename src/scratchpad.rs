@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::state::{load_state, save_state};
+use crate::windows::{focused_window, window_display, WindowId};
+use crate::yabai::{
+    query_spaces, query_window, query_windows, yabai_deminimize_window, yabai_focus_window,
+    yabai_minimize_window, yabai_move_window_id_space, yabai_set_window_frame, yabai_toggle_float,
+};
+
+const SCRATCHPADS_STATE: &str = "scratchpads";
+
+/// Scratchpad windows by name
+type Scratchpads = HashMap<String, WindowId>;
+
+/// Turns the focused window into the scratchpad `name` and hides it
+pub fn send_to_scratchpad(name: &str) {
+    let windows = query_windows();
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let mut scratchpads: Scratchpads = load_state(SCRATCHPADS_STATE);
+    scratchpads.retain(|_, window_id| *window_id != window.id);
+    scratchpads.insert(name.to_string(), window.id);
+    save_state(SCRATCHPADS_STATE, &scratchpads);
+    yabai_minimize_window(window.id);
+}
+
+/// Shows the scratchpad `name` floating in the center of the current space,
+/// or hides it again when it is already shown there
+pub fn toggle_scratchpad(name: &str) {
+    let mut scratchpads: Scratchpads = load_state(SCRATCHPADS_STATE);
+    let Some(window_id) = scratchpads.get(name).copied() else {
+        return;
+    };
+    let Some(window) = query_window(window_id) else {
+        scratchpads.remove(name);
+        save_state(SCRATCHPADS_STATE, &scratchpads);
+        return;
+    };
+    let spaces_infos = query_spaces();
+    let Some(current_space) = spaces_infos.iter().find(|x| x.has_focus) else {
+        return;
+    };
+
+    if window.space == current_space.index as usize && window.is_visible && !window.is_minimized {
+        yabai_minimize_window(window.id);
+        return;
+    }
+    if window.is_minimized {
+        yabai_deminimize_window(window.id);
+    }
+    if window.space != current_space.index as usize {
+        yabai_move_window_id_space(window.id, current_space.index);
+    }
+    if !window.is_floating {
+        yabai_toggle_float(window.id);
+    }
+    if let Some(display) = query_window(window.id).as_ref().and_then(window_display) {
+        yabai_set_window_frame(window.id, &window.frame.centered_in(&display.frame));
+    }
+    yabai_focus_window(window.id);
+}
+
+/// Drops the scratchpads of a destroyed window
+pub fn forget_scratchpad_window(window_id: WindowId) {
+    let mut scratchpads: Scratchpads = load_state(SCRATCHPADS_STATE);
+    let count = scratchpads.len();
+    scratchpads.retain(|_, x| *x != window_id);
+    if scratchpads.len() != count {
+        save_state(SCRATCHPADS_STATE, &scratchpads);
+    }
+}
@@ -1,33 +1,76 @@
-use crate::clap::{SignalEvent, YabaiWindowId};
-use crate::spaces::destroy_all_empty;
+use crate::clap::{SignalEvent, YabaiSpace, YabaiWindowId};
+use crate::config::config;
+use crate::debounce::is_last_event;
+use crate::floating::{center_new_window, forget_float_frame};
+use crate::layouts::relayout_visible_spaces;
+use crate::marks::forget_marked_window;
+use crate::scratchpad::forget_scratchpad_window;
+use crate::spaces::{compact_spaces, destroy_all_empty, remember_created_space};
+use crate::status::notify_status_watchers;
+use crate::switcher::{forget_focused_window, remember_focused_window};
 use crate::windows::auto_focus;
 use crate::yabai::{yabai_add_event, yabai_focus_window, yabai_remove_event, YabaiSignalEvent};
 
-fn signals() -> [YabaiSignalEvent; 4] {
+fn signals() -> [YabaiSignalEvent; 7] {
     [
         YabaiSignalEvent::WindowMinimized,
         YabaiSignalEvent::WindowDestroyed,
         YabaiSignalEvent::WindowMoved,
         YabaiSignalEvent::WindowCreated,
+        YabaiSignalEvent::SpaceCreated,
+        YabaiSignalEvent::WindowFocused,
+        YabaiSignalEvent::SpaceChanged,
     ]
 }
 
-pub fn signal_event_handler(event: &SignalEvent) {
+pub(crate) fn signal_event_handler(event: &SignalEvent) {
+    let debounce = &config().debounce;
     match event {
-        SignalEvent::WindowMinimized(_) => auto_focus(),
+        SignalEvent::WindowMinimized(_)
+            if is_last_event("window-minimized", debounce.window_minimized) =>
+        {
+            auto_focus()
+        }
         SignalEvent::WindowDeminimized(YabaiWindowId { window_id }) => {
             yabai_focus_window(*window_id);
         }
-        SignalEvent::WindowDestroyed(_) => {
+        SignalEvent::WindowDestroyed(YabaiWindowId { window_id }) => {
+            forget_scratchpad_window(*window_id);
+            forget_marked_window(*window_id);
+            forget_focused_window(*window_id);
+            forget_float_frame(*window_id);
+            if !is_last_event("window-destroyed", debounce.window_destroyed) {
+                return;
+            }
             auto_focus();
+            if config().spaces.compact_on_destroy {
+                compact_spaces();
+            }
             destroy_all_empty();
+            relayout_visible_spaces();
+        }
+        SignalEvent::WindowMoved(_) if is_last_event("window-moved", debounce.window_moved) => {
+            destroy_all_empty()
         }
-        SignalEvent::WindowMoved(_) => destroy_all_empty(),
         SignalEvent::WindowCreated(YabaiWindowId { window_id }) => {
+            if config().floating.center_new {
+                center_new_window(*window_id);
+            }
+            if !is_last_event("window-created", debounce.window_created) {
+                return;
+            }
             yabai_focus_window(*window_id);
+            relayout_visible_spaces();
+        }
+        SignalEvent::WindowFocused(YabaiWindowId { window_id }) => {
+            remember_focused_window(*window_id);
+        }
+        SignalEvent::SpaceCreated(YabaiSpace { space_id, .. }) => {
+            remember_created_space(*space_id);
         }
         _ => {}
     }
+    notify_status_watchers();
 }
 
 pub fn load_signal() {
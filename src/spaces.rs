@@ -1,50 +1,588 @@
-use crate::clap::DirectionOrIndex;
-use crate::yabai::{query_spaces, yabai_create_space, yabai_delete_space, yabai_focus_space};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use crate::config::{config, SpacePolicy};
+use crate::lock::lock_spaces;
+use crate::log::{debug, warn};
+use crate::state::{load_state, save_state, timestamp};
+use crate::windows::WindowId;
+use crate::yabai::{
+    query_displays, query_spaces, yabai_create_space, yabai_delete_space, yabai_focus_space,
+    yabai_label_space, yabai_move_space, yabai_move_window_id_space, yabai_send_space_display,
+    YabaiSpaceObject,
+};
 
 pub type SpaceIndex = u8;
+pub type SpaceId = u32;
 
-pub fn focus_space(direction_or_index: &DirectionOrIndex) {
-    let spaces_infos = query_spaces();
-    let current_space = spaces_infos.iter().find(|x| x.has_focus).unwrap();
-    let num_spaces = spaces_infos.len() as u8;
+const CREATED_SPACES_STATE: &str = "created-spaces";
 
-    match direction_or_index {
-        DirectionOrIndex::Left => {
-            if current_space.index > 2 {
-                yabai_focus_space(current_space.index - 1)
-            } else {
-                yabai_focus_space(num_spaces)
-            }
+/// Creation time of recently created spaces, used for the grace period
+type CreatedSpaces = HashMap<SpaceId, u64>;
+
+/// Identifies a space independent of its index, which shifts whenever spaces get
+/// created, moved or destroyed. Commands resolve the current index right before they run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceRef {
+    pub id: SpaceId,
+    pub uuid: String,
+}
+
+impl From<&YabaiSpaceObject> for SpaceRef {
+    fn from(space: &YabaiSpaceObject) -> Self {
+        SpaceRef {
+            id: space.id,
+            uuid: space.uuid.clone(),
         }
-        DirectionOrIndex::Right => {
-            if current_space.index == num_spaces {
-                yabai_focus_space(1)
-            } else {
-                yabai_focus_space(current_space.index + 1)
-            }
+    }
+}
+
+impl SpaceRef {
+    pub fn find<'a>(&self, spaces_infos: &'a [YabaiSpaceObject]) -> Option<&'a YabaiSpaceObject> {
+        spaces_infos
+            .iter()
+            .find(|x| x.id == self.id && x.uuid == self.uuid)
+    }
+
+    /// Current index of the space, `None` once it is gone
+    pub fn index(&self) -> Option<SpaceIndex> {
+        let index = self.find(&query_spaces()).map(|x| x.index);
+        if index.is_none() {
+            debug(format!("Space {} is gone", self.id));
+        }
+        index
+    }
+
+    pub fn focus(&self) {
+        if let Some(index) = self.index() {
+            yabai_focus_space(index);
         }
-        DirectionOrIndex::Index(index) => {
-            if index <= &num_spaces {
-                yabai_focus_space(*index)
-            } else {
-                for _ in num_spaces..*index {
-                    yabai_create_space()
+    }
+
+    pub fn label(&self, label: &str) {
+        if let Some(index) = self.index() {
+            yabai_label_space(index, label);
+        }
+    }
+
+    pub fn move_window(&self, window_id: WindowId) {
+        if let Some(index) = self.index() {
+            yabai_move_window_id_space(window_id, index);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DirectionOrIndex {
+    /// Selecting the space to the Left
+    Left,
+    /// Selecting the space to the Right
+    Right,
+    /// Going to space with `index`
+    Index(u8),
+}
+
+impl FromStr for DirectionOrIndex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(index) = s.parse::<u8>() {
+            Ok(DirectionOrIndex::Index(index))
+        } else if s.to_lowercase() == "left" {
+            Ok(DirectionOrIndex::Left)
+        } else if s.to_lowercase() == "right" {
+            Ok(DirectionOrIndex::Right)
+        } else {
+            Err(format!("invalid value: {}", s))
+        }
+    }
+}
+
+impl DirectionOrIndex {
+    /// Resolves to a 1-based index, cycling through `count` entries from `current`.
+    /// Indexes are passed through unchanged and may exceed `count`.
+    pub fn resolve(&self, current: u8, count: u8) -> u8 {
+        match self {
+            DirectionOrIndex::Left => {
+                if current > 1 {
+                    current - 1
+                } else {
+                    count
                 }
-                yabai_focus_space(*index)
             }
+            DirectionOrIndex::Right => {
+                if current >= count {
+                    1
+                } else {
+                    current + 1
+                }
+            }
+            DirectionOrIndex::Index(index) => *index,
+        }
+    }
+}
+
+/// A space given on the command line
+#[derive(Debug, Clone)]
+pub enum SpaceSelector {
+    DirectionOrIndex(DirectionOrIndex),
+    Label(String),
+}
+
+/// Resolves a direction, index or label to a space index, directions are relative
+/// to the focused space
+pub fn resolve_space(
+    selector: &SpaceSelector,
+    spaces_infos: &[YabaiSpaceObject],
+) -> Option<SpaceIndex> {
+    match selector {
+        SpaceSelector::Label(label) => {
+            let index = spaces_infos
+                .iter()
+                .find(|x| x.label == *label)
+                .map(|x| x.index);
+            if index.is_none() {
+                warn(format!("No space labelled {}", label));
+            }
+            index
+        }
+        SpaceSelector::DirectionOrIndex(direction_or_index) => {
+            let current_space = spaces_infos.iter().find(|x| x.has_focus)?;
+            Some(direction_or_index.resolve(current_space.index, spaces_infos.len() as u8))
+        }
+    }
+}
+
+pub fn focus_space(selector: &SpaceSelector) {
+    let _lock = lock_spaces();
+    let spaces_infos = query_spaces();
+    let num_spaces = spaces_infos.len() as u8;
+
+    let Some(index) = resolve_space(selector, &spaces_infos) else {
+        return;
+    };
+    if index > num_spaces {
+        if let Some(space) = create_spaces(&spaces_infos, index - num_spaces).last() {
+            space.focus();
+        }
+        return;
+    }
+    yabai_focus_space(index)
+}
+
+/// Moves the focused space within its display, yabai can't move spaces across displays.
+/// Directions cycle through the spaces of the focused display.
+pub fn move_space(selector: &SpaceSelector) {
+    let _lock = lock_spaces();
+    let spaces_infos = query_spaces();
+    let Some(current_space) = spaces_infos.iter().find(|x| x.has_focus) else {
+        return;
+    };
+    let displays = spaces_by_display(&spaces_infos);
+    let Some(display_spaces) = displays.get(&current_space.display) else {
+        return;
+    };
+    let Some(position) = display_spaces.iter().position(|x| x.id == current_space.id) else {
+        return;
+    };
+
+    let index = match selector {
+        SpaceSelector::DirectionOrIndex(
+            direction @ (DirectionOrIndex::Left | DirectionOrIndex::Right),
+        ) => {
+            let position = direction.resolve(position as u8 + 1, display_spaces.len() as u8);
+            display_spaces.get(position as usize - 1).map(|x| x.index)
+        }
+        _ => resolve_space(selector, &spaces_infos),
+    };
+    let Some(index) = index else {
+        return;
+    };
+    if !display_spaces.iter().any(|x| x.index == index) {
+        warn(format!(
+            "Space {} is not on display {}, use `space send-display` to move across displays",
+            index, current_space.display
+        ));
+        return;
+    }
+    if index != current_space.index {
+        yabai_move_space(index);
+    }
+}
+
+/// Sends the focused space to another display, directions follow the physical
+/// arrangement of the displays from left to right
+pub fn send_space_to_display(direction_or_index: &DirectionOrIndex) {
+    let _lock = lock_spaces();
+    let spaces_infos = query_spaces();
+    let Some(current_space) = spaces_infos.iter().find(|x| x.has_focus) else {
+        return;
+    };
+    let mut displays = query_displays();
+    displays.sort_by(|a, b| a.frame.x.total_cmp(&b.frame.x));
+    let Some(position) = displays
+        .iter()
+        .position(|x| x.index == current_space.display)
+    else {
+        return;
+    };
+
+    let display = match direction_or_index {
+        DirectionOrIndex::Left | DirectionOrIndex::Right => {
+            let position = direction_or_index.resolve(position as u8 + 1, displays.len() as u8);
+            displays.get(position as usize - 1).map(|x| x.index)
         }
+        DirectionOrIndex::Index(index) => Some(*index as u32),
+    };
+    let Some(display) = display else {
+        return;
+    };
+    if display == current_space.display || !displays.iter().any(|x| x.index == display) {
+        return;
+    }
+    yabai_send_space_display(display as u8);
+    // the space got a new index on its new display
+    SpaceRef::from(current_space).focus();
+}
+
+/// Creates `count` spaces and starts their grace period.
+/// New spaces end up on the focused display, so the indexes of following displays shift.
+pub fn create_spaces(spaces_infos: &[YabaiSpaceObject], count: u8) -> Vec<SpaceRef> {
+    for _ in 0..count {
+        yabai_create_space()
+    }
+    let mut created: Vec<YabaiSpaceObject> = query_spaces()
+        .into_iter()
+        .filter(|space| !spaces_infos.iter().any(|x| x.id == space.id))
+        .collect();
+    created.sort_by_key(|x| x.index);
+    created
+        .iter()
+        .map(|space| {
+            remember_created_space(space.id);
+            SpaceRef::from(space)
+        })
+        .collect()
+}
+
+pub fn remember_created_space(space_id: SpaceId) {
+    let grace_period = config().spaces.grace_period;
+    let now = timestamp();
+    let mut created: CreatedSpaces = load_state(CREATED_SPACES_STATE);
+    created.retain(|_, created_at| *created_at + grace_period > now);
+    created.insert(space_id, now);
+    save_state(CREATED_SPACES_STATE, &created);
+}
+
+fn destroyable_spaces<'a>(
+    spaces: &[&'a YabaiSpaceObject],
+    policy: &SpacePolicy,
+    created: &CreatedSpaces,
+    now: u64,
+) -> Vec<&'a YabaiSpaceObject> {
+    let is_destroyable = |(position, space): &(usize, &&YabaiSpaceObject)| {
+        let labelled = policy.keep_labelled && !space.label.is_empty();
+        let focused = policy.keep_focused && space.has_focus;
+        let in_grace_period = created
+            .get(&space.id)
+            .is_some_and(|created_at| created_at + policy.grace_period > now);
+        space.windows.is_empty()
+            && *position >= policy.keep_first
+            && !labelled
+            && !focused
+            && !in_grace_period
+    };
+    let mut destroyable: Vec<&YabaiSpaceObject> = if policy.trailing_only {
+        let mut trailing: Vec<_> = spaces
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(is_destroyable)
+            .map(|(_, space)| *space)
+            .collect();
+        trailing.reverse();
+        trailing
+    } else {
+        spaces
+            .iter()
+            .enumerate()
+            .filter(is_destroyable)
+            .map(|(_, space)| *space)
+            .collect()
+    };
+    // yabai refuses to destroy the last space of a display
+    if destroyable.len() == spaces.len() {
+        destroyable.remove(0);
+    }
+    destroyable
+}
+
+/// Groups the spaces by display, each display ordered by space index
+fn spaces_by_display(spaces_infos: &[YabaiSpaceObject]) -> BTreeMap<u32, Vec<&YabaiSpaceObject>> {
+    let mut displays: BTreeMap<u32, Vec<&YabaiSpaceObject>> = BTreeMap::new();
+    for space in spaces_infos {
+        displays.entry(space.display).or_default().push(space);
+    }
+    for spaces in displays.values_mut() {
+        spaces.sort_by_key(|x| x.index);
     }
+    displays
 }
 
 pub fn destroy_all_empty() {
-    let mut spaces_infos = query_spaces();
-    let num_spaces = spaces_infos.len();
-    spaces_infos.retain(|x| x.windows.is_empty());
-    spaces_infos.sort_by(|a, b| a.index.partial_cmp(&b.index).unwrap());
-    if num_spaces == spaces_infos.len() {
-        spaces_infos.pop();
-    }
-    for space in spaces_infos.iter().rev() {
-        yabai_delete_space(space.index);
+    let _lock = lock_spaces();
+    let policy = &config().spaces;
+    let created: CreatedSpaces = load_state(CREATED_SPACES_STATE);
+    let now = timestamp();
+    let spaces_infos = query_spaces();
+
+    let mut destroyable = Vec::new();
+    for spaces in spaces_by_display(&spaces_infos).values() {
+        destroyable.append(&mut destroyable_spaces(spaces, policy, &created, now));
+    }
+    destroyable.sort_by_key(|x| x.index);
+    let destroyable: Vec<SpaceRef> = destroyable.into_iter().map(SpaceRef::from).collect();
+    for space in destroyable.iter().rev() {
+        match space.find(&query_spaces()) {
+            Some(current) if current.windows.is_empty() => yabai_delete_space(current.index),
+            _ => debug(format!("Space {} changed, not destroying it", space.id)),
+        }
+    }
+}
+
+/// Pairs of (target, source) for every space of a display whose windows and label
+/// end up elsewhere when compacting. Occupied spaces move to the front keeping their
+/// order, empty spaces to the back, so labels of empty spaces survive as well.
+fn compaction<'a>(
+    spaces: &[&'a YabaiSpaceObject],
+) -> Vec<(&'a YabaiSpaceObject, &'a YabaiSpaceObject)> {
+    let (occupied, empty): (Vec<&&YabaiSpaceObject>, Vec<_>) =
+        spaces.iter().partition(|x| !x.windows.is_empty());
+    spaces
+        .iter()
+        .zip(occupied.into_iter().chain(empty))
+        .filter(|(target, source)| target.id != source.id)
+        .map(|(target, source)| (*target, *source))
+        .collect()
+}
+
+/// Moves windows so that the occupied spaces of every display are contiguous,
+/// keeping their relative order and labels
+pub fn compact_spaces() {
+    let _lock = lock_spaces();
+    let spaces_infos = query_spaces();
+    let mut focus = None;
+
+    for spaces in spaces_by_display(&spaces_infos).values() {
+        let moves = compaction(spaces);
+        for (target, source) in &moves {
+            let target_ref = SpaceRef::from(*target);
+            for window_id in &source.windows {
+                target_ref.move_window(*window_id);
+            }
+            if source.has_focus && !source.windows.is_empty() {
+                focus = Some(target_ref);
+            }
+        }
+        // labels are unique, so free all of them before handing them over
+        let relabelled: Vec<_> = moves
+            .iter()
+            .filter(|(target, source)| target.label != source.label)
+            .collect();
+        for (target, _) in relabelled.iter().filter(|(x, _)| !x.label.is_empty()) {
+            SpaceRef::from(*target).label("");
+        }
+        for (target, source) in relabelled.iter().filter(|(_, x)| !x.label.is_empty()) {
+            SpaceRef::from(*target).label(&source.label);
+        }
+    }
+    if let Some(space) = focus {
+        space.focus();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn space(id: SpaceId, windows: Vec<WindowId>) -> YabaiSpaceObject {
+        YabaiSpaceObject {
+            id,
+            index: id as SpaceIndex,
+            windows,
+            ..Default::default()
+        }
+    }
+
+    fn policy() -> SpacePolicy {
+        SpacePolicy {
+            keep_labelled: false,
+            keep_first: 0,
+            trailing_only: false,
+            keep_focused: false,
+            grace_period: 0,
+            compact_on_destroy: false,
+        }
+    }
+
+    fn destroyable_ids(
+        spaces: &[YabaiSpaceObject],
+        policy: &SpacePolicy,
+        created: &CreatedSpaces,
+        now: u64,
+    ) -> Vec<SpaceId> {
+        let spaces: Vec<&YabaiSpaceObject> = spaces.iter().collect();
+        destroyable_spaces(&spaces, policy, created, now)
+            .iter()
+            .map(|x| x.id)
+            .collect()
+    }
+
+    #[test]
+    fn destroys_empty_spaces_only() {
+        let spaces = [space(1, vec![10]), space(2, vec![]), space(3, vec![11])];
+        let ids = destroyable_ids(&spaces, &policy(), &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn keeps_one_space_per_display() {
+        let spaces = [space(1, vec![]), space(2, vec![])];
+        let ids = destroyable_ids(&spaces, &policy(), &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn keeps_labelled_spaces() {
+        let mut labelled = space(2, vec![]);
+        labelled.label = "code".to_string();
+        let spaces = [space(1, vec![10]), labelled, space(3, vec![])];
+
+        let ids = destroyable_ids(&spaces, &policy(), &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![2, 3]);
+        let policy = SpacePolicy {
+            keep_labelled: true,
+            ..policy()
+        };
+        let ids = destroyable_ids(&spaces, &policy, &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[test]
+    fn keeps_first_spaces() {
+        let spaces = [
+            space(1, vec![]),
+            space(2, vec![]),
+            space(3, vec![10]),
+            space(4, vec![]),
+        ];
+        let policy = SpacePolicy {
+            keep_first: 2,
+            ..policy()
+        };
+        let ids = destroyable_ids(&spaces, &policy, &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![4]);
+    }
+
+    #[test]
+    fn trailing_only_stops_at_the_last_occupied_space() {
+        let spaces = [
+            space(1, vec![10]),
+            space(2, vec![]),
+            space(3, vec![11]),
+            space(4, vec![]),
+            space(5, vec![]),
+        ];
+        let policy = SpacePolicy {
+            trailing_only: true,
+            ..policy()
+        };
+        let ids = destroyable_ids(&spaces, &policy, &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![4, 5]);
+    }
+
+    #[test]
+    fn keeps_focused_space() {
+        let mut focused = space(2, vec![]);
+        focused.has_focus = true;
+        let spaces = [space(1, vec![10]), focused, space(3, vec![])];
+
+        let ids = destroyable_ids(&spaces, &policy(), &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![2, 3]);
+        let policy = SpacePolicy {
+            keep_focused: true,
+            ..policy()
+        };
+        let ids = destroyable_ids(&spaces, &policy, &CreatedSpaces::new(), 0);
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[test]
+    fn keeps_spaces_in_grace_period() {
+        let spaces = [space(1, vec![10]), space(2, vec![]), space(3, vec![])];
+        let created = CreatedSpaces::from([(2, 100), (3, 90)]);
+        let policy = SpacePolicy {
+            grace_period: 5,
+            ..policy()
+        };
+        assert_eq!(destroyable_ids(&spaces, &policy, &created, 104), vec![3]);
+        assert_eq!(destroyable_ids(&spaces, &policy, &created, 105), vec![2, 3]);
+    }
+
+    fn labelled(id: SpaceId, windows: Vec<WindowId>, label: &str) -> YabaiSpaceObject {
+        YabaiSpaceObject {
+            label: label.to_string(),
+            ..space(id, windows)
+        }
+    }
+
+    fn compaction_of(spaces: &[YabaiSpaceObject]) -> Vec<(SpaceId, SpaceId, String)> {
+        let spaces: Vec<&YabaiSpaceObject> = spaces.iter().collect();
+        compaction(&spaces)
+            .iter()
+            .map(|(target, source)| (target.id, source.id, source.label.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn compaction_keeps_compact_spaces() {
+        let spaces = [space(1, vec![10]), space(2, vec![11]), space(3, vec![])];
+        assert!(compaction_of(&spaces).is_empty());
+    }
+
+    #[test]
+    fn compaction_keeps_order_of_occupied_spaces() {
+        let spaces = [
+            space(1, vec![]),
+            labelled(2, vec![10], "code"),
+            space(3, vec![]),
+            labelled(4, vec![11], "web"),
+        ];
+        assert_eq!(
+            compaction_of(&spaces),
+            vec![
+                (1, 2, "code".to_string()),
+                (2, 4, "web".to_string()),
+                (3, 1, String::new()),
+                (4, 3, String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn compaction_moves_labels_of_empty_spaces_to_the_back() {
+        let spaces = [
+            labelled(1, vec![], "x"),
+            space(2, vec![10]),
+            space(3, vec![11]),
+        ];
+        assert_eq!(
+            compaction_of(&spaces),
+            vec![
+                (1, 2, String::new()),
+                (2, 3, String::new()),
+                (3, 1, "x".to_string()),
+            ]
+        );
     }
 }
@@ -0,0 +1,43 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::backend::backend;
+use crate::log::warn;
+
+/// Loads state persisted by an earlier invocation, falling back to the default
+pub fn load_state<T>(name: &str) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    let Some(content) = backend().read_state(name) else {
+        return T::default();
+    };
+    match serde_json::from_slice(&content) {
+        Ok(state) => state,
+        Err(e) => {
+            warn(format!("Discarding corrupt state {}: {}", name, e));
+            T::default()
+        }
+    }
+}
+
+pub fn save_state<T>(name: &str, state: &T)
+where
+    T: Serialize,
+{
+    let result = serde_json::to_vec(state)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            backend()
+                .write_state(name, &content)
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn(format!("Failed to save state {}: {}", name, e));
+    }
+}
+
+/// Seconds since the unix epoch
+pub fn timestamp() -> u64 {
+    backend().timestamp()
+}
@@ -0,0 +1,190 @@
+use std::env::temp_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::backend::backend;
+use crate::log::warn;
+use crate::spaces::SpaceIndex;
+use crate::windows::WindowId;
+use crate::yabai::{query_all_windows, query_displays, query_spaces};
+
+const STATUS_FILENAME: &str = "yabai-utils-status";
+const SKETCHYBAR_EVENT: &str = "yabai_utils_status";
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum StatusFormat {
+    Json,
+    /// `sketchybar --trigger` commands, to be piped into a shell
+    Sketchybar,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct SpaceStatus {
+    index: SpaceIndex,
+    label: String,
+    windows: usize,
+    occupied: bool,
+    visible: bool,
+    focused: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct DisplayStatus {
+    index: u32,
+    spaces: Vec<SpaceStatus>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct FocusedWindow {
+    id: WindowId,
+    app: String,
+    title: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Status {
+    displays: Vec<DisplayStatus>,
+    focused_space: Option<SpaceIndex>,
+    focused_window: Option<FocusedWindow>,
+    windows: usize,
+}
+
+fn status_path() -> PathBuf {
+    temp_dir().join(Path::new(STATUS_FILENAME))
+}
+
+fn current_status() -> Status {
+    let spaces_infos = query_spaces();
+    let windows = query_all_windows();
+    let mut displays = query_displays();
+    displays.sort_by_key(|x| x.index);
+
+    let displays = displays
+        .iter()
+        .map(|display| {
+            let mut spaces: Vec<_> = spaces_infos
+                .iter()
+                .filter(|x| x.display == display.index)
+                .collect();
+            spaces.sort_by_key(|x| x.index);
+            DisplayStatus {
+                index: display.index,
+                spaces: spaces
+                    .iter()
+                    .map(|space| SpaceStatus {
+                        index: space.index,
+                        label: space.label.clone(),
+                        windows: space.windows.len(),
+                        occupied: !space.windows.is_empty(),
+                        visible: space.is_visible,
+                        focused: space.has_focus,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Status {
+        displays,
+        focused_space: spaces_infos.iter().find(|x| x.has_focus).map(|x| x.index),
+        focused_window: windows
+            .iter()
+            .find(|x| x.has_focus)
+            .map(|window| FocusedWindow {
+                id: window.id,
+                app: window.app.clone(),
+                title: window.title.clone(),
+            }),
+        windows: windows.len(),
+    }
+}
+
+/// Quotes `value` as a single shell word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn render(status: &Status, format: StatusFormat) -> Result<String, String> {
+    let json = serde_json::to_string(status).map_err(|e| e.to_string())?;
+    match format {
+        StatusFormat::Json => Ok(json),
+        StatusFormat::Sketchybar => {
+            let focused_window = status.focused_window.as_ref();
+            let variables = [
+                (
+                    "FOCUSED_SPACE",
+                    status
+                        .focused_space
+                        .map(|x| x.to_string())
+                        .unwrap_or_default(),
+                ),
+                (
+                    "FOCUSED_APP",
+                    focused_window.map(|x| x.app.clone()).unwrap_or_default(),
+                ),
+                (
+                    "FOCUSED_TITLE",
+                    focused_window.map(|x| x.title.clone()).unwrap_or_default(),
+                ),
+                ("WINDOWS", status.windows.to_string()),
+                ("STATUS", json),
+            ];
+            let variables: Vec<String> = variables
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, shell_quote(value)))
+                .collect();
+            Ok(format!(
+                "sketchybar --trigger {} {}",
+                SKETCHYBAR_EVENT,
+                variables.join(" ")
+            ))
+        }
+    }
+}
+
+/// Tells running `status --watch` invocations that the state changed
+pub fn notify_status_watchers() {
+    if !backend().is_live() {
+        return;
+    }
+    let now = SystemTime::now();
+    if let Err(e) = fs::File::create(status_path()).and_then(|x| x.set_modified(now)) {
+        warn(format!("Failed to notify status watchers: {}", e));
+    }
+}
+
+fn last_notification() -> Option<SystemTime> {
+    fs::metadata(status_path()).and_then(|x| x.modified()).ok()
+}
+
+/// Prints the status once, or with `watch` again whenever a signal reports a change
+pub fn print_status(format: StatusFormat, watch: bool) -> Result<(), String> {
+    let mut last_output = render(&current_status(), format)?;
+    println!("{}", last_output);
+    if !watch {
+        return Ok(());
+    }
+    let mut seen = last_notification();
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let notification = last_notification();
+        if notification == seen {
+            continue;
+        }
+        seen = notification;
+        let output = render(&current_status(), format)?;
+        // many signals don't change anything the bar shows
+        if output != last_output {
+            println!("{}", output);
+            last_output = output;
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::state::{load_state, save_state};
+use crate::windows::{focus_window_anywhere, WindowId};
+use crate::yabai::{query_all_windows, query_window};
+
+const FOCUS_HISTORY_STATE: &str = "focus-history";
+const FOCUS_HISTORY_LENGTH: usize = 100;
+
+/// Window ids, most recently focused first
+type FocusHistory = Vec<WindowId>;
+
+#[derive(Serialize)]
+struct WindowEntry<'a> {
+    id: WindowId,
+    app: &'a str,
+    title: &'a str,
+    space: usize,
+}
+
+pub fn remember_focused_window(window_id: WindowId) {
+    let mut history: FocusHistory = load_state(FOCUS_HISTORY_STATE);
+    history.retain(|x| *x != window_id);
+    history.insert(0, window_id);
+    history.truncate(FOCUS_HISTORY_LENGTH);
+    save_state(FOCUS_HISTORY_STATE, &history);
+}
+
+pub fn forget_focused_window(window_id: WindowId) {
+    let mut history: FocusHistory = load_state(FOCUS_HISTORY_STATE);
+    let count = history.len();
+    history.retain(|x| *x != window_id);
+    if history.len() != count {
+        save_state(FOCUS_HISTORY_STATE, &history);
+    }
+}
+
+/// Tabs and line breaks would break the picker columns
+fn sanitize(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Prints all windows, most recently focused first. `picker` prints
+/// `id\tapp\ttitle\tspace` lines for choose or fzf instead of JSON.
+pub fn list_windows(picker: bool) {
+    let history: FocusHistory = load_state(FOCUS_HISTORY_STATE);
+    let mut windows = query_all_windows();
+    windows.sort_by_key(|x| {
+        let recency = history.iter().position(|id| *id == x.id);
+        (recency.is_none(), recency, x.id)
+    });
+
+    if picker {
+        for window in &windows {
+            println!(
+                "{}\t{}\t{}\t{}",
+                window.id,
+                sanitize(&window.app),
+                sanitize(&window.title),
+                window.space
+            );
+        }
+        return;
+    }
+    let entries: Vec<WindowEntry> = windows
+        .iter()
+        .map(|window| WindowEntry {
+            id: window.id,
+            app: &window.app,
+            title: &window.title,
+            space: window.space,
+        })
+        .collect();
+    match serde_json::to_string(&entries) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Focuses the window with `window_id`, switching to its space first
+pub fn focus_window_id(window_id: WindowId) {
+    if let Some(window) = query_window(window_id) {
+        focus_window_anywhere(&window);
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+use std::env::temp_dir;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{backend, set_backend, Backend};
+use crate::clap::{Cli, Commands, SignalCommand, SignalEvent};
+use crate::config::config;
+use crate::log::{format_timestamp, rotate, warn};
+use crate::signal::signal_event_handler;
+
+const TRACE_FILENAME: &str = "yabai-utils-trace.jsonl";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TracedQuery {
+    pub message: String,
+    pub response: String,
+}
+
+/// Everything a single signal event saw and did
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub timestamp: String,
+    /// Event arguments as passed to `signal event`
+    pub event: Vec<String>,
+    /// Seconds since the unix epoch, as the handler saw them
+    #[serde(default)]
+    pub time: u64,
+    /// Persisted state by name, as the handler first read it
+    #[serde(default)]
+    pub states: BTreeMap<String, String>,
+    pub queries: Vec<TracedQuery>,
+    pub actions: Vec<String>,
+    /// Whether a later event of the same kind took over, skipping the expensive work
+    #[serde(default)]
+    pub coalesced: bool,
+}
+
+fn trace_path() -> PathBuf {
+    config()
+        .trace
+        .path
+        .clone()
+        .unwrap_or_else(|| temp_dir().join(Path::new(TRACE_FILENAME)))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Passes everything through to `inner`, recording what the handler saw and did.
+/// The clock stands still while recording, so replays see the same time.
+struct Recorder {
+    inner: Arc<dyn Backend>,
+    entry: Mutex<TraceEntry>,
+}
+
+impl Recorder {
+    fn new(inner: Arc<dyn Backend>, event: Vec<String>) -> Self {
+        let time = inner.timestamp();
+        Recorder {
+            inner,
+            entry: Mutex::new(TraceEntry {
+                timestamp: format_timestamp(SystemTime::now()),
+                event,
+                time,
+                states: BTreeMap::new(),
+                queries: Vec::new(),
+                actions: Vec::new(),
+                coalesced: false,
+            }),
+        }
+    }
+}
+
+impl Backend for Recorder {
+    fn query(&self, message: &str) -> io::Result<Vec<u8>> {
+        let response = self.inner.query(message)?;
+        lock(&self.entry).queries.push(TracedQuery {
+            message: message.to_string(),
+            response: String::from_utf8_lossy(&response).into_owned(),
+        });
+        Ok(response)
+    }
+
+    fn send(&self, message: &[&str]) -> io::Result<Output> {
+        lock(&self.entry).actions.push(message.join(" "));
+        self.inner.send(message)
+    }
+
+    fn read_state(&self, name: &str) -> Option<Vec<u8>> {
+        let content = self.inner.read_state(name)?;
+        lock(&self.entry)
+            .states
+            .entry(name.to_string())
+            .or_insert_with(|| String::from_utf8_lossy(&content).into_owned());
+        Some(content)
+    }
+
+    fn write_state(&self, name: &str, content: &[u8]) -> io::Result<()> {
+        self.inner.write_state(name, content)
+    }
+
+    fn timestamp(&self) -> u64 {
+        lock(&self.entry).time
+    }
+
+    fn is_last_event(&self, kind: &str, delay_ms: u64) -> bool {
+        let last = self.inner.is_last_event(kind, delay_ms);
+        if !last {
+            lock(&self.entry).coalesced = true;
+        }
+        last
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+}
+
+#[derive(Default)]
+struct ReplayState {
+    queries: Vec<TracedQuery>,
+    states: BTreeMap<String, Vec<u8>>,
+    actions: Vec<String>,
+    /// First query the recording has no response for, the replay stops there
+    unrecorded: Option<String>,
+}
+
+/// Serves a recorded trace entry instead of yabai, the state files and the clock.
+/// Commands are collected instead of sent, state is only saved in memory.
+struct Replay {
+    time: u64,
+    coalesced: bool,
+    state: Mutex<ReplayState>,
+}
+
+impl Replay {
+    fn new(entry: &TraceEntry) -> Self {
+        Replay {
+            time: entry.time,
+            coalesced: entry.coalesced,
+            state: Mutex::new(ReplayState {
+                queries: entry.queries.clone(),
+                states: entry
+                    .states
+                    .iter()
+                    .map(|(name, content)| (name.clone(), content.clone().into_bytes()))
+                    .collect(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl Backend for Replay {
+    fn query(&self, message: &str) -> io::Result<Vec<u8>> {
+        let mut state = lock(&self.state);
+        if state.unrecorded.is_some() {
+            return Err(io::Error::other("replay stopped"));
+        }
+        // handlers may query in a different order than they did when recording
+        match state.queries.iter().position(|x| x.message == message) {
+            Some(position) => Ok(state.queries.remove(position).response.into_bytes()),
+            None => {
+                state.unrecorded = Some(message.to_string());
+                Err(io::Error::other(format!("unrecorded query {}", message)))
+            }
+        }
+    }
+
+    fn send(&self, message: &[&str]) -> io::Result<Output> {
+        let mut state = lock(&self.state);
+        if state.unrecorded.is_some() {
+            return Err(io::Error::other("replay stopped"));
+        }
+        state.actions.push(message.join(" "));
+        Ok(Output {
+            status: ExitStatus::default(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn read_state(&self, name: &str) -> Option<Vec<u8>> {
+        lock(&self.state).states.get(name).cloned()
+    }
+
+    fn write_state(&self, name: &str, content: &[u8]) -> io::Result<()> {
+        lock(&self.state)
+            .states
+            .insert(name.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.time
+    }
+
+    /// Events lose the debounce race exactly like they did when recording
+    fn is_last_event(&self, _kind: &str, _delay_ms: u64) -> bool {
+        !self.coalesced
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+fn write_entry(entry: &TraceEntry) -> Result<(), String> {
+    let settings = &config().trace;
+    let path = trace_path();
+    if fs::metadata(&path).is_ok_and(|x| x.len() >= settings.max_size) {
+        rotate(&path, settings.keep);
+    }
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let mut file = File::options()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+/// Runs the handler for `event` on top of the current backend, recording a trace entry
+fn record_event(event: &SignalEvent, args: Vec<String>) -> TraceEntry {
+    let recorder = Arc::new(Recorder::new(backend(), args));
+    let previous = set_backend(recorder.clone());
+    signal_event_handler(event);
+    set_backend(previous);
+    let entry = lock(&recorder.entry).clone();
+    entry
+}
+
+/// Handles a signal event, recording what it saw and did into the trace file
+pub fn traced_signal_event_handler(event: &SignalEvent, args: Vec<String>) {
+    if !config().trace.enabled {
+        signal_event_handler(event);
+        return;
+    }
+    let entry = record_event(event, args);
+    if let Err(e) = write_entry(&entry) {
+        warn(format!("Failed to write trace: {}", e));
+    }
+}
+
+/// Runs the handler for `event` against `entry`, returning the actions it took
+fn replay_event(event: &SignalEvent, entry: &TraceEntry) -> Result<Vec<String>, String> {
+    let replay = Arc::new(Replay::new(entry));
+    let previous = set_backend(replay.clone());
+    signal_event_handler(event);
+    set_backend(previous);
+    let state = std::mem::take(&mut *lock(&replay.state));
+    match state.unrecorded {
+        Some(message) => Err(format!("unrecorded query {}", message)),
+        None => Ok(state.actions),
+    }
+}
+
+fn parse_event(args: &[String]) -> Result<SignalEvent, String> {
+    let argv = ["yabai_utils", "signal", "event"]
+        .into_iter()
+        .map(String::from)
+        .chain(args.iter().cloned());
+    match Cli::try_parse_from(argv)
+        .map_err(|e| e.to_string())?
+        .command
+    {
+        Commands::Signal(arg) => match arg.command {
+            SignalCommand::Event(signal) => Ok(signal.event),
+            _ => Err("not a signal event".to_string()),
+        },
+        _ => Err("not a signal event".to_string()),
+    }
+}
+
+/// Feeds the events of a trace file through the signal handler against the recorded
+/// yabai state, printing the actions taken and how they differ from the recording
+pub fn replay_trace(path: &Path) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: TraceEntry =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        let event = parse_event(&entry.event).map_err(|e| format!("line {}: {}", number + 1, e))?;
+
+        println!("{} {}", entry.timestamp, entry.event.join(" "));
+        let actions =
+            replay_event(&event, &entry).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        if actions == entry.actions {
+            for action in &actions {
+                println!("    yabai -m {}", action);
+            }
+        } else {
+            for action in &entry.actions {
+                println!("  - yabai -m {}", action);
+            }
+            for action in &actions {
+                println!("  + yabai -m {}", action);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::clap::YabaiWindowId;
+    use crate::yabai::YabaiSpaceObject;
+
+    /// The backend is process wide, so tests swapping it take turns
+    static BACKEND_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Three spaces on one display, only the focused first one holds a window
+    fn spaces() -> String {
+        let spaces: Vec<YabaiSpaceObject> = (1..=3)
+            .map(|i| YabaiSpaceObject {
+                id: i,
+                uuid: i.to_string(),
+                index: i as u8,
+                display: 1,
+                windows: if i == 1 { vec![10] } else { Vec::new() },
+                has_focus: i == 1,
+                ..Default::default()
+            })
+            .collect();
+        serde_json::to_string(&spaces).unwrap()
+    }
+
+    /// Answers every query with the same response, like a yabai that doesn't change
+    struct Fake {
+        responses: HashMap<&'static str, String>,
+        states: HashMap<&'static str, &'static str>,
+        /// Whether a later event takes over every debounced one
+        coalesced: bool,
+    }
+
+    impl Backend for Fake {
+        fn query(&self, message: &str) -> io::Result<Vec<u8>> {
+            match self.responses.get(message) {
+                Some(response) => Ok(response.as_bytes().to_vec()),
+                None => Err(io::Error::other("no response")),
+            }
+        }
+
+        fn send(&self, _message: &[&str]) -> io::Result<Output> {
+            Ok(Output {
+                status: ExitStatus::default(),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
+
+        fn read_state(&self, name: &str) -> Option<Vec<u8>> {
+            self.states.get(name).map(|x| x.as_bytes().to_vec())
+        }
+
+        fn write_state(&self, _name: &str, _content: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn timestamp(&self) -> u64 {
+            1000
+        }
+
+        fn is_last_event(&self, _kind: &str, _delay_ms: u64) -> bool {
+            !self.coalesced
+        }
+
+        fn is_live(&self) -> bool {
+            false
+        }
+    }
+
+    fn window_moved() -> SignalEvent {
+        SignalEvent::WindowMoved(YabaiWindowId { window_id: 10 })
+    }
+
+    #[test]
+    fn replays_recorded_events() {
+        let _guard = lock(&BACKEND_LOCK);
+        let fake = Fake {
+            responses: HashMap::from([("query --spaces", spaces())]),
+            // space 3 was created two seconds ago and is still in its grace period
+            states: HashMap::from([("created-spaces", r#"{"3": 998}"#)]),
+            coalesced: false,
+        };
+        let previous = set_backend(Arc::new(fake));
+        let args = vec!["window-moved".to_string(), "10".to_string()];
+        let entry = record_event(&window_moved(), args.clone());
+        set_backend(previous);
+
+        assert_eq!(entry.event, args);
+        assert_eq!(entry.time, 1000);
+        assert_eq!(entry.states["created-spaces"], r#"{"3": 998}"#);
+        assert_eq!(entry.actions, vec!["space 2 --destroy".to_string()]);
+        assert!(entry.queries.iter().all(|x| x.message == "query --spaces"));
+
+        // the trace file round trip must not lose anything replays depend on
+        let line = serde_json::to_string(&entry).unwrap();
+        let entry: TraceEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(replay_event(&window_moved(), &entry), Ok(entry.actions));
+    }
+
+    #[test]
+    fn replay_stops_at_unrecorded_queries() {
+        let _guard = lock(&BACKEND_LOCK);
+        let entry = TraceEntry {
+            timestamp: String::new(),
+            event: vec!["window-moved".to_string(), "10".to_string()],
+            time: 1000,
+            states: BTreeMap::new(),
+            queries: Vec::new(),
+            actions: vec!["space 2 --destroy".to_string()],
+            coalesced: false,
+        };
+        assert_eq!(
+            replay_event(&window_moved(), &entry),
+            Err("unrecorded query query --spaces".to_string())
+        );
+    }
+
+    #[test]
+    fn replays_coalesced_events() {
+        let _guard = lock(&BACKEND_LOCK);
+        let fake = Fake {
+            responses: HashMap::from([("query --spaces", spaces())]),
+            states: HashMap::new(),
+            coalesced: true,
+        };
+        let previous = set_backend(Arc::new(fake));
+        let args = vec!["window-moved".to_string(), "10".to_string()];
+        let entry = record_event(&window_moved(), args);
+        set_backend(previous);
+
+        // a later event did the work, this one neither queried nor acted
+        assert!(entry.coalesced);
+        assert!(entry.queries.is_empty());
+        assert!(entry.actions.is_empty());
+        assert_eq!(replay_event(&window_moved(), &entry), Ok(Vec::new()));
+    }
+}
@@ -1,11 +1,18 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::ops::Sub;
+use std::process::Command;
+use std::str::FromStr;
 
-use crate::clap::DirectionOrIndex;
+use crate::config::config;
+use crate::lock::lock_spaces;
+use crate::log::{error, warn};
+use crate::spaces::{create_spaces, resolve_space, SpaceIndex, SpaceRef, SpaceSelector};
 use crate::yabai::{
-    query_spaces, query_windows, yabai_create_space, yabai_focus_space, yabai_focus_window,
-    yabai_move_window_space, yabai_resize_window, YabaiWindowObject,
+    query_all_windows, query_displays, query_spaces, query_windows, yabai_deminimize_window,
+    yabai_focus_space, yabai_focus_window, yabai_focus_window_selector, yabai_resize_window,
+    yabai_resize_window_edge, yabai_set_window_frame, yabai_stack_window, yabai_swap_window,
+    yabai_toggle_zoom, yabai_warp_window, YabaiDisplayObject, YabaiWindowFrame, YabaiWindowObject,
 };
 
 pub type WindowId = usize;
@@ -18,6 +25,39 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Left => Direction::Right,
+            Direction::Down => Direction::Up,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    fn is_horizontal(&self) -> bool {
+        matches!(self, Direction::Left | Direction::Right)
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+pub enum Zoom {
+    /// Filling the area of the parent node
+    Parent,
+    /// Filling the whole space
+    Fullscreen,
+    /// Moving the window into its own native fullscreen space
+    Native,
+}
+
 #[derive(Debug)]
 pub struct Position {
     pub x: f64,
@@ -54,7 +94,42 @@ impl Position {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
+pub enum ResizeAmount {
+    Pixels(f64),
+    Percent(f64),
+}
+
+impl FromStr for ResizeAmount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let amount = match s.strip_suffix('%') {
+            Some(percent) => percent.parse::<f64>().map(ResizeAmount::Percent),
+            None => s
+                .strip_suffix("px")
+                .unwrap_or(s)
+                .parse::<f64>()
+                .map(ResizeAmount::Pixels),
+        };
+        match amount {
+            Ok(amount) => Ok(amount),
+            Err(_) => Err(format!("invalid value: {}", s)),
+        }
+    }
+}
+
+impl ResizeAmount {
+    /// Resolves the amount to pixels, percentages are relative to `length`
+    pub fn pixels(&self, length: f64) -> f64 {
+        match self {
+            ResizeAmount::Pixels(pixels) => *pixels,
+            ResizeAmount::Percent(percent) => length * percent / 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct WindowNeighbours {
     pub up: Option<WindowId>,
     pub right: Option<WindowId>,
@@ -73,7 +148,65 @@ impl WindowNeighbours {
     }
 }
 
+/// Windows of a stack share one frame, so they are collapsed into a single node.
+/// Returns the nodes and the node of every stacked window that got collapsed.
+fn stack_nodes(
+    windows: &[YabaiWindowObject],
+) -> (Vec<YabaiWindowObject>, HashMap<WindowId, WindowId>) {
+    let same_frame = |a: &YabaiWindowFrame, b: &YabaiWindowFrame| {
+        (a.x - b.x).abs() < 1.0
+            && (a.y - b.y).abs() < 1.0
+            && (a.w - b.w).abs() < 1.0
+            && (a.h - b.h).abs() < 1.0
+    };
+    let mut stacks: Vec<Vec<&YabaiWindowObject>> = Vec::new();
+    let mut nodes = Vec::new();
+    for window in windows {
+        if window.stack_index == 0 {
+            nodes.push(window.clone());
+            continue;
+        }
+        match stacks
+            .iter_mut()
+            .find(|x| same_frame(&x[0].frame, &window.frame))
+        {
+            Some(stack) => stack.push(window),
+            None => stacks.push(vec![window]),
+        }
+    }
+    let mut members = HashMap::new();
+    for stack in stacks {
+        let node = stack
+            .iter()
+            .find(|x| x.has_focus)
+            .or(stack.iter().min_by_key(|x| x.stack_index))
+            .unwrap();
+        for window in &stack {
+            if window.id != node.id {
+                members.insert(window.id, node.id);
+            }
+        }
+        nodes.push((*node).clone());
+    }
+    (nodes, members)
+}
+
+/// Directional neighbours of every window, a stack counts as a single window
 pub fn new_window_order(windows: &[YabaiWindowObject]) -> HashMap<WindowId, WindowNeighbours> {
+    let (nodes, members) = stack_nodes(windows);
+    let mut results = window_order(&nodes);
+    for (window_id, node_id) in members {
+        if let Some(neighbours) = results.get(&node_id).cloned() {
+            results.insert(window_id, neighbours);
+        }
+    }
+    results
+}
+
+fn window_order(windows: &[YabaiWindowObject]) -> HashMap<WindowId, WindowNeighbours> {
+    if windows.is_empty() {
+        return HashMap::new();
+    }
     let fuzz = 15.0;
     let mut windows_hash = HashMap::new();
 
@@ -273,10 +406,106 @@ pub fn focused_window(windows: &[YabaiWindowObject]) -> Option<&YabaiWindowObjec
     windows.iter().find(|x| x.has_focus)
 }
 
+pub fn window_display(window: &YabaiWindowObject) -> Option<YabaiDisplayObject> {
+    query_displays()
+        .into_iter()
+        .find(|x| x.index as usize == window.display)
+}
+
 pub fn resize_window(direction: Direction, offset: i32) {
     yabai_resize_window(direction, offset);
 }
 
+/// Distance between the window edge and the display edge in `direction`
+fn edge_room(frame: &YabaiWindowFrame, display: &YabaiWindowFrame, direction: &Direction) -> f64 {
+    match direction {
+        Direction::Up => frame.y - display.y,
+        Direction::Left => frame.x - display.x,
+        Direction::Down => (display.y + display.h) - (frame.y + frame.h),
+        Direction::Right => (display.x + display.w) - (frame.x + frame.w),
+    }
+}
+
+/// Grows or shrinks the focused window towards `direction`.
+/// Tiled windows can only move edges shared with a neighbour and floating windows
+/// can't grow past the display, in both cases the opposite edge is used instead.
+pub fn smart_resize_window(grow: bool, direction: &Direction, amount: &ResizeAmount) {
+    let fuzz = 15.0;
+    let mut windows = query_windows();
+    windows.retain(|x| x.is_visible && !x.is_hidden);
+    let Some(window) = focused_window(&windows) else {
+        return;
+    };
+    let Some(display) = window_display(window) else {
+        return;
+    };
+    let length = if direction.is_horizontal() {
+        display.frame.w
+    } else {
+        display.frame.h
+    };
+    let pixels = amount.pixels(length);
+
+    let can_move = |edge: &Direction| {
+        if window.is_floating {
+            !grow || edge_room(&window.frame, &display.frame, edge) > fuzz
+        } else {
+            let mut tiled = windows.clone();
+            tiled.retain(|x| !x.is_floating);
+            new_window_order(&tiled)
+                .get(&window.id)
+                .is_some_and(|x| x.neigbour(edge).is_some())
+        }
+    };
+    let edge = if can_move(direction) {
+        direction.clone()
+    } else {
+        direction.opposite()
+    };
+    // moving an edge outwards grows the window
+    let outwards = match edge {
+        Direction::Up | Direction::Left => -pixels,
+        Direction::Down | Direction::Right => pixels,
+    };
+    yabai_resize_window_edge(&edge, if grow { outwards } else { -outwards });
+}
+
+/// Focuses a window on any space or display, switching to its space first
+pub fn focus_window_anywhere(window: &YabaiWindowObject) {
+    if window.is_minimized {
+        yabai_deminimize_window(window.id);
+    } else if !window.is_visible {
+        yabai_focus_space(window.space as SpaceIndex);
+    }
+    yabai_focus_window(window.id);
+}
+
+fn launch_app(app: &str, launch: Option<&str>) {
+    let command = match launch.or(config().launch.get(app).map(String::as_str)) {
+        Some(command) => Command::new("sh").arg("-c").arg(command).spawn(),
+        None => Command::new("open").arg("-a").arg(app).spawn(),
+    };
+    if let Err(e) = command {
+        error(format!("Failed to launch {}: {}", app, e));
+    }
+}
+
+/// Focuses a window of `app` on any space, cycling through its windows on repeated calls.
+/// Launches the app when it has no windows.
+pub fn focus_or_launch_app(app: &str, launch: Option<&str>) {
+    let mut windows = query_all_windows();
+    windows.retain(|x| x.app.eq_ignore_ascii_case(app));
+    windows.sort_by_key(|x| x.id);
+    let next_window = match windows.iter().position(|x| x.has_focus) {
+        Some(position) => windows.get((position + 1) % windows.len()),
+        None => windows.iter().find(|x| x.is_visible).or(windows.first()),
+    };
+    match next_window {
+        Some(window) => focus_window_anywhere(window),
+        None => launch_app(app, launch),
+    }
+}
+
 pub fn auto_focus() {
     let mut windows = query_windows();
     windows.retain(|x| x.is_visible && !x.is_hidden);
@@ -294,56 +523,129 @@ pub fn auto_focus() {
     }
 }
 
-pub fn focus_window_by_direction(direction: &Direction, ignore_sticky: bool) {
-    let mut windows = query_windows();
-    windows.retain(|x| x.is_visible && !x.is_hidden && (!x.is_sticky || ignore_sticky));
-    let current_window = focused_window(&windows).unwrap();
+/// Returns the focused window and its neighbour in `direction`
+fn window_and_neighbour(
+    direction: &Direction,
+    include_sticky: bool,
+) -> Option<(YabaiWindowObject, YabaiWindowObject)> {
+    let query = || {
+        let mut windows = query_windows();
+        windows.retain(|x| x.is_visible && !x.is_hidden && (!x.is_sticky || include_sticky));
+        windows
+    };
+    let mut windows = query();
+    // a zoomed window overlaps its neighbours, so step out of the zoom first
+    if let Some(window) = focused_window(&windows) {
+        if window.has_fullscreen_zoom {
+            yabai_toggle_zoom(&Zoom::Fullscreen);
+            windows = query();
+        } else if window.has_parent_zoom {
+            yabai_toggle_zoom(&Zoom::Parent);
+            windows = query();
+        }
+    }
+    // windows covered by a zoomed window can't be seen, so skip them
+    let zoomed: Vec<YabaiWindowFrame> = windows
+        .iter()
+        .filter(|x| x.has_fullscreen_zoom || x.has_parent_zoom)
+        .map(|x| x.frame.clone())
+        .collect();
+    windows.retain(|x| {
+        x.has_focus
+            || x.has_fullscreen_zoom
+            || x.has_parent_zoom
+            || !zoomed.iter().any(|zoom| zoom.contains(&x.frame))
+    });
+    let current_window = focused_window(&windows)?;
     let store = new_window_order(&windows);
-    let window = store.get(&current_window.id).unwrap();
-    if let Some(neighbour_id) = window.neigbour(direction) {
-        yabai_focus_window(neighbour_id)
+    let neighbour_id = store.get(&current_window.id)?.neigbour(direction)?;
+    let neighbour = windows.iter().find(|x| x.id == neighbour_id)?;
+    Some((current_window.clone(), neighbour.clone()))
+}
+
+pub fn toggle_zoom(zoom: &Zoom) {
+    yabai_toggle_zoom(zoom);
+}
+
+pub fn focus_window_by_direction(direction: &Direction, include_sticky: bool) {
+    if let Some((_, neighbour)) = window_and_neighbour(direction, include_sticky) {
+        yabai_focus_window(neighbour.id)
+    }
+}
+
+/// Floating windows are not part of the layout tree, so they trade frames instead of
+/// swapping places
+fn swap_window_frames(window: &YabaiWindowObject, other: &YabaiWindowObject) {
+    yabai_set_window_frame(window.id, &other.frame);
+    yabai_set_window_frame(other.id, &window.frame);
+}
+
+/// Focuses the next or previous window of the focused stack, wrapping around
+pub fn focus_stack(next: bool) {
+    let (step, wrap) = if next {
+        ("stack.next", "stack.first")
+    } else {
+        ("stack.prev", "stack.last")
+    };
+    if !yabai_focus_window_selector(step) {
+        yabai_focus_window_selector(wrap);
+    }
+}
+
+/// Stacks the focused window onto its neighbour in `direction`
+pub fn stack_window_by_direction(direction: &Direction, include_sticky: bool) {
+    if let Some((window, neighbour)) = window_and_neighbour(direction, include_sticky) {
+        yabai_stack_window(neighbour.id, window.id);
+    }
+}
+
+pub fn swap_window_by_direction(direction: &Direction, include_sticky: bool) {
+    if let Some((window, neighbour)) = window_and_neighbour(direction, include_sticky) {
+        // a tiled window can't be placed freely and yabai only swaps tiled windows
+        match (window.is_floating, neighbour.is_floating) {
+            (true, true) => swap_window_frames(&window, &neighbour),
+            (false, false) => yabai_swap_window(neighbour.id),
+            _ => warn("A floating and a tiled window can't be swapped".to_string()),
+        }
     }
 }
 
-pub fn move_window_to_space(direction_or_index: &DirectionOrIndex, follow_focus: bool) {
+/// Warping re-inserts into the layout tree, which floating windows are not part of
+pub fn warp_window_by_direction(direction: &Direction, include_sticky: bool) {
+    if let Some((window, neighbour)) = window_and_neighbour(direction, include_sticky) {
+        if window.is_floating || neighbour.is_floating {
+            warn("Floating windows can't be warped, swap them instead".to_string());
+        } else {
+            yabai_warp_window(neighbour.id);
+        }
+    }
+}
+
+pub fn move_window_to_space(selector: &SpaceSelector, follow_focus: bool) {
+    let _lock = lock_spaces();
     let spaces_infos = query_spaces();
     let windows = query_windows();
-    let focused_window = focused_window(&windows);
-    if focused_window.is_none() {
+    let Some(window) = focused_window(&windows) else {
         return;
-    }
+    };
     let num_spaces = spaces_infos.len() as u8;
-    let index = match direction_or_index {
-        DirectionOrIndex::Left => {
-            let current_space = spaces_infos.iter().find(|x| x.has_focus).unwrap();
-            if current_space.index > 2 {
-                current_space.index - 1
-            } else {
-                num_spaces
-            }
-        }
-        DirectionOrIndex::Right => {
-            let current_space = spaces_infos.iter().find(|x| x.has_focus).unwrap();
-            if current_space.index == num_spaces {
-                1
-            } else {
-                current_space.index + 1
-            }
-        }
-        DirectionOrIndex::Index(index) => {
-            if index <= &num_spaces {
-                *index
-            } else {
-                for _ in num_spaces..*index {
-                    yabai_create_space()
-                }
-                *index
-            }
-        }
+    let Some(index) = resolve_space(selector, &spaces_infos) else {
+        return;
+    };
+    let target = if index > num_spaces {
+        create_spaces(&spaces_infos, index - num_spaces).pop()
+    } else {
+        spaces_infos
+            .iter()
+            .find(|x| x.index == index)
+            .map(SpaceRef::from)
+    };
+    let Some(target) = target else {
+        return;
     };
-    yabai_move_window_space(index);
+    target.move_window(window.id);
     if follow_focus {
-        yabai_focus_space(index);
-        yabai_focus_window(focused_window.unwrap().id);
+        target.focus();
+        yabai_focus_window(window.id);
     }
 }
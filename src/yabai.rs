@@ -3,83 +3,118 @@ use std::process::{Command, Output};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-use crate::spaces::SpaceIndex;
-use crate::windows::{Direction, Position, WindowId};
+use crate::backend::backend;
+use crate::client::Client;
+use crate::log::warn;
+use crate::spaces::{SpaceId, SpaceIndex};
+use crate::windows::{Direction, Position, WindowId, Zoom};
 
-#[derive(Serialize, Deserialize, Debug)]
+// The models follow the output of `yabai -m query`. Fields every supported yabai
+// release reports are required, fields only some releases report are optional or
+// fall back to their default. Unknown fields are ignored.
+
+/// A signal as listed by `yabai -m signal --list`
+#[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "kebab-case")]
-struct YabaiSignal {
-    index: u32,
-    label: String,
-    app: String,
-    event: YabaiSignalEvent,
-    action: String,
+pub struct YabaiSignal {
+    pub index: u32,
+    pub label: String,
+    pub app: String,
+    pub title: String,
+    pub active: Option<bool>,
+    pub event: YabaiSignalEvent,
+    pub action: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-enum SpaceType {
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub enum SpaceType {
     #[serde(rename = "managed")]
     Managed,
+    #[default]
     #[serde(rename = "bsp")]
     Bsp,
+    #[serde(rename = "stack")]
+    Stack,
     #[serde(rename = "float")]
     Float,
+    /// A layout this version doesn't know about
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct YabaiSpaceObject {
-    id: u32,
-    uuid: String,
+    pub id: SpaceId,
+    pub uuid: String,
     pub index: SpaceIndex,
-    label: String,
+    pub label: String,
     #[serde(rename = "type")]
-    space_type: SpaceType,
-    display: u32,
+    pub space_type: SpaceType,
+    pub display: u32,
     pub windows: Vec<WindowId>,
-    first_window: WindowId,
-    last_window: WindowId,
+    pub first_window: WindowId,
+    pub last_window: WindowId,
     pub has_focus: bool,
-    is_visible: bool,
-    is_native_fullscreen: bool,
+    pub is_visible: bool,
+    pub is_native_fullscreen: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct YabaiWindowObject {
     pub id: usize,
-    pid: usize,
+    pub pid: usize,
     pub app: String,
     pub title: String,
     pub frame: YabaiWindowFrame,
-    root_window: bool,
-    display: usize,
-    space: usize,
-    pub level: usize,
-    sub_level: isize,
-    layer: String,
-    sub_layer: String,
-    opacity: f32,
-    split_type: String,
-    split_child: String,
-    stack_index: usize,
-    can_move: bool,
-    can_resize: bool,
+    pub role: String,
+    pub subrole: String,
+    pub root_window: Option<bool>,
+    pub display: usize,
+    pub space: usize,
+    pub level: isize,
+    pub sub_level: Option<isize>,
+    pub layer: Option<String>,
+    pub sub_layer: Option<String>,
+    pub opacity: f32,
+    pub split_type: String,
+    pub split_child: Option<String>,
+    pub stack_index: usize,
+    pub scratchpad: Option<String>,
+    pub can_move: bool,
+    pub can_resize: bool,
     pub has_focus: bool,
-    has_shadow: bool,
-    has_parent_zoom: bool,
-    has_fullscreen_zoom: bool,
-    has_ax_reference: bool,
-    is_native_fullscreen: bool,
+    pub has_shadow: bool,
+    pub has_border: Option<bool>,
+    pub has_parent_zoom: bool,
+    pub has_fullscreen_zoom: bool,
+    pub has_ax_reference: Option<bool>,
+    pub is_native_fullscreen: bool,
     pub is_visible: bool,
     pub is_minimized: bool,
     pub is_hidden: bool,
     pub is_floating: bool,
     pub is_sticky: bool,
-    is_grabbed: bool,
+    pub is_topmost: Option<bool>,
+    pub is_grabbed: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct YabaiDisplayObject {
+    pub id: u32,
+    pub uuid: String,
+    pub index: u32,
+    /// Missing before yabai 6
+    #[serde(default)]
+    pub label: String,
+    pub frame: YabaiWindowFrame,
+    pub spaces: Vec<SpaceIndex>,
+    pub has_focus: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct YabaiWindowFrame {
     pub x: f64,
     pub y: f64,
@@ -98,6 +133,27 @@ impl YabaiWindowFrame {
             y: self.y + (self.h / 2.0),
         }
     }
+
+    /// Whether `other` lies within this frame
+    pub fn contains(&self, other: &YabaiWindowFrame) -> bool {
+        let fuzz = 1.0;
+        other.x + fuzz >= self.x
+            && other.y + fuzz >= self.y
+            && other.x + other.w <= self.x + self.w + fuzz
+            && other.y + other.h <= self.y + self.h + fuzz
+    }
+
+    /// Same size, centered on `other` but never larger than it
+    pub fn centered_in(&self, other: &YabaiWindowFrame) -> YabaiWindowFrame {
+        let w = self.w.min(other.w);
+        let h = self.h.min(other.h);
+        YabaiWindowFrame {
+            x: other.x + (other.w - w) / 2.0,
+            y: other.y + (other.h - h) / 2.0,
+            w,
+            h,
+        }
+    }
 }
 
 impl std::cmp::PartialEq for YabaiWindowFrame {
@@ -114,64 +170,177 @@ impl std::cmp::PartialOrd for YabaiWindowFrame {
     }
 }
 
-pub fn query_windows() -> Vec<YabaiWindowObject> {
-    query_yabai::<YabaiWindowObject>("query --windows --space").unwrap()
+/// Commands carry on with nothing to work on when yabai can't be queried
+fn or_empty<T>(result: Result<Vec<T>, YabaiQueryError>) -> Vec<T> {
+    result.unwrap_or_else(|e| {
+        warn(format!("Failed to query yabai: {}", e));
+        Vec::new()
+    })
+}
+
+pub(crate) fn query_windows() -> Vec<YabaiWindowObject> {
+    or_empty(Client.space_windows(None))
+}
+
+pub(crate) fn query_all_windows() -> Vec<YabaiWindowObject> {
+    or_empty(Client.windows())
+}
+
+pub(crate) fn query_window(window_id: WindowId) -> Option<YabaiWindowObject> {
+    Client.window(window_id).ok()
+}
+
+pub(crate) fn query_space_windows(space_index: SpaceIndex) -> Vec<YabaiWindowObject> {
+    or_empty(Client.space_windows(Some(space_index)))
+}
+
+pub(crate) fn query_spaces() -> Vec<YabaiSpaceObject> {
+    or_empty(Client.spaces())
 }
 
-pub fn query_spaces() -> Vec<YabaiSpaceObject> {
-    query_yabai("query --spaces").unwrap()
+pub(crate) fn query_displays() -> Vec<YabaiDisplayObject> {
+    or_empty(Client.displays())
 }
 
 #[derive(Debug)]
 pub enum YabaiQueryError {
-    CommandExecutionError,
-    ResponseParsingError,
+    CommandExecutionError(std::io::Error),
+    ResponseParsingError(serde_json::Error),
+}
+
+impl std::fmt::Display for YabaiQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YabaiQueryError::CommandExecutionError(e) => write!(f, "failed to run yabai: {}", e),
+            YabaiQueryError::ResponseParsingError(e) => {
+                write!(f, "unexpected response from yabai: {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for YabaiQueryError {}
+
+#[derive(Debug)]
+pub enum YabaiCommandError {
+    CommandExecutionError(std::io::Error),
+    /// yabai refused the command, with its error message
+    Rejected(String),
+}
+
+impl std::fmt::Display for YabaiCommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YabaiCommandError::CommandExecutionError(e) => write!(f, "failed to run yabai: {}", e),
+            YabaiCommandError::Rejected(message) => write!(f, "yabai refused: {}", message),
+        }
+    }
 }
 
-fn query_yabai<T>(message: &str) -> Result<Vec<T>, YabaiQueryError>
+impl std::error::Error for YabaiCommandError {}
+
+pub(crate) fn query_yabai<T>(message: &str) -> Result<Vec<T>, YabaiQueryError>
 where
     T: for<'a> Deserialize<'a>,
 {
-    let mut args = Vec::new();
-    args.push("-m");
-    args.append(&mut Iterator::collect(message.split_whitespace()));
-    let result = match Command::new("yabai").args(args).output() {
-        Ok(stream) => stream.stdout,
-        Err(_) => return Err(YabaiQueryError::CommandExecutionError),
-    };
+    query_yabai_object(message)
+}
 
-    match serde_json::from_slice(&result) {
-        Ok(result) => Ok(result),
-        Err(_) => Err(YabaiQueryError::ResponseParsingError),
-    }
+/// Queries that select a single entity, like `--window <id>`, respond with an object
+pub(crate) fn query_yabai_object<T>(message: &str) -> Result<T, YabaiQueryError>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let result = backend()
+        .query(message)
+        .map_err(YabaiQueryError::CommandExecutionError)?;
+    serde_json::from_slice(&result).map_err(YabaiQueryError::ResponseParsingError)
 }
 
 fn send_yabai(message: &str) -> Result<Output, std::io::Error> {
-    let mut args = Vec::new();
-    args.push("-m");
-    args.append(&mut Iterator::collect(message.split_whitespace()));
-    let output = Command::new("yabai").args(args).output()?;
-    Ok(output)
+    send_yabai_args(message.split_whitespace().collect())
+}
+
+/// Like `send_yabai`, for arguments that may be empty or contain whitespace
+pub(crate) fn send_yabai_args(message: Vec<&str>) -> Result<Output, std::io::Error> {
+    backend().send(&message)
 }
 
 pub fn yabai_focus_window(window_id: WindowId) {
-    let _ = send_yabai(format!("window --focus {}", &window_id).as_str());
+    let _ = Client.focus_window(window_id);
+}
+
+/// Focuses a window by selector like `stack.next`, returns whether yabai accepted it
+pub fn yabai_focus_window_selector(selector: &str) -> bool {
+    Client.focus_window_selector(selector).is_ok()
+}
+
+pub fn yabai_stack_window(target: WindowId, window_id: WindowId) {
+    let _ = Client.stack_window_onto(target, window_id);
+}
+
+pub fn yabai_toggle_zoom(zoom: &Zoom) {
+    let _ = Client.toggle_zoom(zoom);
+}
+
+pub fn yabai_swap_window(window_id: WindowId) {
+    let _ = Client.swap_window(window_id);
+}
+
+pub fn yabai_warp_window(window_id: WindowId) {
+    let _ = Client.warp_window(window_id);
+}
+
+pub fn yabai_set_window_frame(window_id: WindowId, frame: &YabaiWindowFrame) {
+    let _ = Client.set_window_frame(window_id, frame);
+}
+
+pub fn yabai_toggle_float(window_id: WindowId) {
+    let _ = Client.toggle_float(window_id);
+}
+
+pub fn yabai_toggle_sticky(window_id: WindowId) {
+    let _ = Client.toggle_sticky(window_id);
+}
+
+pub fn yabai_minimize_window(window_id: WindowId) {
+    let _ = Client.minimize_window(window_id);
+}
+
+pub fn yabai_deminimize_window(window_id: WindowId) {
+    let _ = Client.deminimize_window(window_id);
 }
 
 pub fn yabai_focus_space(space_index: SpaceIndex) {
-    let _ = send_yabai(format!("space --focus {}", &space_index).as_str());
+    let _ = Client.focus_space(space_index);
+}
+
+pub fn yabai_move_window_id_space(window_id: WindowId, space_index: SpaceIndex) {
+    let _ = Client.move_window_to_space(window_id, space_index);
 }
 
-pub fn yabai_move_window_space(space_index: SpaceIndex) {
-    let _ = send_yabai(format!("window --space {}", &space_index).as_str());
+pub fn yabai_label_space(space_index: SpaceIndex, label: &str) {
+    let _ = Client.label_space(space_index, label);
 }
 
 pub fn yabai_delete_space(space_index: SpaceIndex) {
-    let _ = send_yabai(format!("space {} --destroy", &space_index).as_str());
+    let _ = Client.destroy_space(space_index);
+}
+
+pub fn yabai_move_space(space_index: SpaceIndex) {
+    let _ = Client.move_space(space_index);
+}
+
+pub fn yabai_send_space_display(display_index: u8) {
+    let _ = Client.send_space_to_display(display_index);
+}
+
+pub fn yabai_set_space_layout(space_index: SpaceIndex, layout: &str) {
+    let _ = Client.set_space_layout(space_index, layout);
 }
 
 pub fn yabai_create_space() {
-    let _ = send_yabai("space  --create");
+    let _ = Client.create_space();
 }
 
 pub fn yabai_resize_window(direction: Direction, offset: i32) {
@@ -184,8 +353,19 @@ pub fn yabai_resize_window(direction: Direction, offset: i32) {
     let _ = send_yabai(format!("window --resize {}:{}", corner, offset).as_str());
 }
 
+/// Moves a single edge of the focused window by `offset` along its axis
+pub fn yabai_resize_window_edge(edge: &Direction, offset: f64) {
+    let (handle, offset) = match edge {
+        Direction::Up => ("top", format!("0:{:.0}", offset)),
+        Direction::Left => ("left", format!("{:.0}:0", offset)),
+        Direction::Down => ("bottom", format!("0:{:.0}", offset)),
+        Direction::Right => ("right", format!("{:.0}:0", offset)),
+    };
+    let _ = send_yabai(format!("window --resize {}:{}", handle, offset).as_str());
+}
+
 #[allow(dead_code)]
-#[derive(Serialize, Deserialize, Debug, PartialEq, ValueEnum, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, ValueEnum, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum YabaiSignalEvent {
     ApplicationLaunched,
@@ -217,6 +397,11 @@ pub enum YabaiSignalEvent {
     DockDidRestart,
     MenuBarHiddenChanged,
     SystemWoke,
+    /// An event this version doesn't know about
+    #[default]
+    #[serde(other)]
+    #[value(skip)]
+    Unknown,
 }
 
 impl std::fmt::Display for YabaiSignalEvent {
@@ -251,6 +436,7 @@ impl std::fmt::Display for YabaiSignalEvent {
             YabaiSignalEvent::DockDidRestart => write!(f, "dock_did_restart"),
             YabaiSignalEvent::MenuBarHiddenChanged => write!(f, "menu_bar_hidden_changed"),
             YabaiSignalEvent::SystemWoke => write!(f, "system_woke"),
+            YabaiSignalEvent::Unknown => write!(f, "unknown"),
         }
     }
 }
@@ -315,8 +501,7 @@ pub fn yabai_add_event(event: YabaiSignalEvent) {
 }
 
 fn query_signal_events() -> Vec<YabaiSignal> {
-    let output = send_yabai("signal --list").unwrap();
-    serde_json::from_slice(&output.stdout).unwrap()
+    or_empty(Client.signals())
 }
 
 pub fn yabai_remove_event(event: &YabaiSignalEvent) {
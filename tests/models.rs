@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use yabai_utils::windows::{new_window_order, Direction};
+use yabai_utils::yabai::{
+    SpaceType, YabaiDisplayObject, YabaiSignal, YabaiSignalEvent, YabaiSpaceObject,
+    YabaiWindowObject,
+};
+
+const VERSIONS: [&str; 3] = ["4.0", "6.0", "7.1"];
+
+fn fixture<T: DeserializeOwned>(version: &str, name: &str) -> T {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "fixtures",
+        &format!("yabai-{}", version),
+        &format!("{}.json", name),
+    ]
+    .iter()
+    .collect();
+    let content = fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&content).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn windows_of_all_versions_parse() {
+    for version in VERSIONS {
+        let windows: Vec<YabaiWindowObject> = fixture(version, "windows");
+        assert_eq!(windows.len(), 3, "yabai {}", version);
+
+        let focused = windows.iter().find(|x| x.has_focus).unwrap();
+        assert_eq!(focused.id, 3387);
+        assert_eq!(focused.app, "Terminal");
+        assert_eq!(focused.frame.w, 705.0);
+
+        let floating = windows.iter().find(|x| x.is_floating).unwrap();
+        assert_eq!(floating.space, 2);
+        assert_eq!(floating.level, 3);
+    }
+}
+
+#[test]
+fn version_specific_window_fields_are_optional() {
+    let old: Vec<YabaiWindowObject> = fixture("4.0", "windows");
+    assert_eq!(old[0].sub_level, None);
+    assert_eq!(old[0].has_ax_reference, None);
+    assert_eq!(old[0].is_topmost, Some(false));
+
+    let new: Vec<YabaiWindowObject> = fixture("7.1", "windows");
+    assert_eq!(new[0].sub_level, Some(0));
+    assert_eq!(new[0].is_grabbed, None);
+    assert_eq!(new[0].scratchpad.as_deref(), Some(""));
+}
+
+#[test]
+fn spaces_of_all_versions_parse() {
+    for version in VERSIONS {
+        let spaces: Vec<YabaiSpaceObject> = fixture(version, "spaces");
+        assert_eq!(spaces.len(), 3, "yabai {}", version);
+        assert_eq!(spaces[0].label, "code");
+        assert!(spaces[0].has_focus);
+        assert_eq!(spaces[0].windows, vec![3387, 3401]);
+        assert!(spaces[2].windows.is_empty());
+    }
+    let spaces: Vec<YabaiSpaceObject> = fixture("6.0", "spaces");
+    assert_eq!(spaces[1].space_type, SpaceType::Stack);
+}
+
+#[test]
+fn displays_of_all_versions_parse() {
+    for version in VERSIONS {
+        let displays: Vec<YabaiDisplayObject> = fixture(version, "displays");
+        assert_eq!(displays.len(), 1, "yabai {}", version);
+        assert_eq!(displays[0].index, 1);
+        assert_eq!(displays[0].spaces, vec![1, 2, 3]);
+        assert_eq!(displays[0].frame.w, 1440.0);
+    }
+    let displays: Vec<YabaiDisplayObject> = fixture("7.1", "displays");
+    assert_eq!(displays[0].has_focus, Some(true));
+}
+
+#[test]
+fn signals_of_all_versions_parse() {
+    for version in VERSIONS {
+        let signals: Vec<YabaiSignal> = fixture(version, "signals");
+        assert_eq!(signals.len(), 1, "yabai {}", version);
+        assert_eq!(signals[0].event, YabaiSignalEvent::WindowFocused);
+        assert!(signals[0].label.starts_with("yabai-utils"));
+    }
+}
+
+/// The first object of a fixture as plain JSON, to tamper with
+fn first_object(version: &str, name: &str) -> Map<String, Value> {
+    let objects: Vec<Map<String, Value>> = fixture(version, name);
+    objects.into_iter().next().unwrap()
+}
+
+#[test]
+fn unknown_fields_and_values_are_tolerated() {
+    let mut window = first_object("7.1", "windows");
+    window.insert("has-halo".to_string(), Value::Bool(true));
+    let window: YabaiWindowObject = serde_json::from_value(Value::Object(window)).unwrap();
+    assert_eq!(window.id, 3387);
+
+    let mut space = first_object("7.1", "spaces");
+    space.insert("type".to_string(), Value::from("spiral"));
+    let space: YabaiSpaceObject = serde_json::from_value(Value::Object(space)).unwrap();
+    assert_eq!(space.space_type, SpaceType::Unknown);
+
+    let mut signal = first_object("7.1", "signals");
+    signal.insert("event".to_string(), Value::from("window_teleported"));
+    let signal: YabaiSignal = serde_json::from_value(Value::Object(signal)).unwrap();
+    assert_eq!(signal.event, YabaiSignalEvent::Unknown);
+}
+
+#[test]
+fn missing_required_fields_are_rejected() {
+    let mut window = first_object("7.1", "windows");
+    window.remove("id");
+    assert!(serde_json::from_value::<YabaiWindowObject>(Value::Object(window)).is_err());
+
+    let mut space = first_object("7.1", "spaces");
+    space.remove("uuid");
+    assert!(serde_json::from_value::<YabaiSpaceObject>(Value::Object(space)).is_err());
+
+    let mut display = first_object("7.1", "displays");
+    display.remove("frame");
+    assert!(serde_json::from_value::<YabaiDisplayObject>(Value::Object(display)).is_err());
+}
+
+#[test]
+fn window_order_of_fixture() {
+    for version in VERSIONS {
+        let windows: Vec<YabaiWindowObject> = fixture(version, "windows");
+        let visible: Vec<YabaiWindowObject> =
+            windows.into_iter().filter(|x| x.space == 1).collect();
+        let order = new_window_order(&visible);
+        assert_eq!(order[&3387].neigbour(&Direction::Right), Some(3401));
+        assert_eq!(order[&3401].neigbour(&Direction::Left), Some(3387));
+        assert_eq!(order[&3387].neigbour(&Direction::Left), None);
+    }
+}
+
+#[test]
+fn window_order_of_empty_space() {
+    assert!(new_window_order(&[]).is_empty());
+}